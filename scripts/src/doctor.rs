@@ -0,0 +1,139 @@
+//! `doctor`: checks the environment the other subcommands rely on.
+//!
+//! Each check prints PASS or FAIL with the reason, in order: the Arbitrum RPC at
+//! `ARB_URL` answers, its chain id matches `NETWORK` (default `arbitrum-sepolia`),
+//! the `PRIVATE_KEY` account holds some ETH, `CONTRACT_ADDRESS` has code, and the
+//! relay answers `latestHeight`.
+
+use ethers::prelude::*;
+use std::env;
+use std::error::Error;
+use std::sync::Arc;
+
+use crate::{contract_address, provider, BtcVerifier};
+
+/// Chain ids of the networks `NETWORK` may name.
+const NETWORKS: [(&str, u64); 3] = [("arbitrum-one", 42161), ("arbitrum-nova", 42170), ("arbitrum-sepolia", 421614)];
+
+pub async fn run() -> Result<(), Box<dyn Error>> {
+    let mut failures = 0;
+    let mut report = |name: &str, result: Result<String, String>| match result {
+        Ok(detail) => println!("[PASS] {name}: {detail}"),
+        Err(reason) => {
+            failures += 1;
+            println!("[FAIL] {name}: {reason}");
+        }
+    };
+
+    let provider = match provider() {
+        Ok(provider) => provider,
+        Err(err) => {
+            report("RPC reachable", Err(err.to_string()));
+            return Err("1 check failed".into());
+        }
+    };
+    let chain_id = provider.get_chainid().await.map_err(|err| format!("no answer from ARB_URL: {err}"));
+    report("RPC reachable", chain_id.as_ref().map(|id| format!("chain id {id}")).map_err(Clone::clone));
+    if let Ok(chain_id) = chain_id {
+        let network = env::var("NETWORK").unwrap_or_else(|_| "arbitrum-sepolia".to_string());
+        report("chain id matches NETWORK", check_network(&network, chain_id.as_u64()));
+    }
+    report("wallet funded", check_balance(&provider).await);
+
+    match contract_address() {
+        Ok(address) => {
+            let has_code = check_code(&provider, address).await;
+            let deployed = has_code.is_ok();
+            report("contract deployed", has_code);
+            if deployed {
+                report("relay responds", check_relay(provider, address).await);
+            }
+        }
+        Err(err) => report("contract deployed", Err(format!("invalid CONTRACT_ADDRESS: {err}"))),
+    }
+
+    match failures {
+        0 => Ok(()),
+        n => Err(format!("{n} check(s) failed").into()),
+    }
+}
+
+/// Name of the Arbitrum network with `chain_id`, if it's one the relay is deployed to.
+pub fn network_name(chain_id: u64) -> Option<&'static str> {
+    NETWORKS.iter().find(|(_, id)| *id == chain_id).map(|(name, _)| *name)
+}
+
+fn check_network(network: &str, chain_id: u64) -> Result<String, String> {
+    let expected = NETWORKS
+        .iter()
+        .find(|(name, _)| *name == network)
+        .map(|(_, id)| *id)
+        .ok_or_else(|| format!("unknown NETWORK `{network}`"))?;
+    if expected == chain_id {
+        Ok(format!("{network} ({chain_id})"))
+    } else {
+        Err(format!("ARB_URL is on chain {chain_id}, but NETWORK={network} expects {expected}"))
+    }
+}
+
+async fn check_balance<M: Middleware>(client: &M) -> Result<String, String> {
+    let key = env::var("PRIVATE_KEY").map_err(|_| "PRIVATE_KEY is not set".to_string())?;
+    let wallet: LocalWallet = key.parse().map_err(|_| "PRIVATE_KEY is not a valid key".to_string())?;
+    let balance = client.get_balance(wallet.address(), None).await.map_err(|err| err.to_string())?;
+    if balance.is_zero() {
+        return Err(format!("{:?} has no ETH to pay for gas", wallet.address()));
+    }
+    Ok(format!("{:?} holds {} wei", wallet.address(), balance))
+}
+
+async fn check_code<M: Middleware>(client: &M, address: Address) -> Result<String, String> {
+    let code = client.get_code(address, None).await.map_err(|err| err.to_string())?;
+    if code.is_empty() {
+        return Err(format!("no contract code at {address:?}; check CONTRACT_ADDRESS and NETWORK"));
+    }
+    Ok(format!("{} bytes of code at {address:?}", code.len()))
+}
+
+async fn check_relay<M: Middleware + 'static>(client: M, address: Address) -> Result<String, String> {
+    let relay = BtcVerifier::new(address, Arc::new(client));
+    match relay.latest_height().call().await {
+        Ok(height) => Ok(format!("tip at height {height}")),
+        Err(err) => Err(crate::revert::explain(err).to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_rpc::tests::stub_node;
+
+    /// Answers every JSON-RPC request with an empty result, as a node does for
+    /// `eth_getCode` at an address with no contract.
+    fn empty_chain(request: &str) -> (u16, String) {
+        let id = request.split(r#""id":"#).nth(1).and_then(|rest| rest.split(',').next()).unwrap_or("1");
+        (200, format!(r#"{{"jsonrpc":"2.0","id":{id},"result":"0x"}}"#))
+    }
+
+    #[tokio::test]
+    async fn test_missing_contract_code() {
+        let provider = Provider::<Http>::try_from(stub_node(empty_chain).await).unwrap();
+        let address: Address = "0xb4864bb622f3020a5d424ff2cc20738b3327f7e2".parse().unwrap();
+        let err = check_code(&provider, address).await.unwrap_err();
+        assert_eq!(
+            err,
+            "no contract code at 0xb4864bb622f3020a5d424ff2cc20738b3327f7e2; check CONTRACT_ADDRESS and NETWORK"
+        );
+    }
+
+    #[test]
+    fn test_network_mismatch() {
+        assert_eq!(check_network("arbitrum-sepolia", 421614), Ok("arbitrum-sepolia (421614)".to_string()));
+        assert_eq!(
+            check_network("arbitrum-one", 421614),
+            Err("ARB_URL is on chain 421614, but NETWORK=arbitrum-one expects 42161".to_string())
+        );
+        assert!(check_network("mainnet", 1).is_err());
+        assert_eq!(network_name(42170), Some("arbitrum-nova"));
+        assert_eq!(network_name(1), None);
+    }
+}
@@ -3,48 +3,120 @@ use std::sync::Arc;
 use dotenv::dotenv;
 use std::env;
 
+mod bitcoin_rpc;
+mod doctor;
+mod offline;
+mod prove;
+mod revert;
+mod sync;
+
+// Define the contract's functions. Stylus exports methods in camelCase,
+// so `hash_btc_header` is called as `hashBtcHeader`.
+abigen!(
+    BtcVerifier,
+    r#"[
+        function hashBtcHeader(string memory header_hex) public view returns (string memory)
+        function submitHeaders(string[] memory headers) external returns (uint32)
+        function syncCursor() external view returns (uint32)
+        function latestHeight() external view returns (uint32)
+        function verifyTxAtHeight(string memory txid_hex, uint32 height, string[] memory proof, uint32 index, uint32 min_confirmations) external view returns (bool)
+    ]"#
+);
+
+pub type Client = SignerMiddleware<Provider<Http>, LocalWallet>;
+
+// Address of the deployed contract; override with CONTRACT_ADDRESS.
+const DEFAULT_CONTRACT_ADDRESS: &str = "0xb4864bb622f3020a5d424ff2cc20738b3327f7e2";
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenv().ok();
-    
-    let rpc_url = env::var("ARB_URL").expect("ARB_URL must be set");
+
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let offline = args.iter().any(|arg| arg == "--offline");
+    args.retain(|arg| arg != "--offline");
+
+    match args.first().map(String::as_str) {
+        Some("broadcast-raw") => return offline::broadcast_raw(&args[1..]).await,
+        Some("doctor") => return doctor::run().await,
+        _ => {}
+    }
+    if offline {
+        run(connect_offline()?, &args, true).await
+    } else {
+        run(connect().await?, &args, false).await
+    }
+}
+
+async fn run<M: Middleware + 'static>(
+    contract: BtcVerifier<M>,
+    args: &[String],
+    offline: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        None | Some("hash") => hash_header(contract, offline).await,
+        Some("sync") => sync::run(contract, &args[1..], offline).await,
+        Some("prove") => prove::run(contract, &args[1..]).await,
+        Some(other) => {
+            Err(format!("unknown command `{other}` (expected `hash`, `sync`, `prove`, `broadcast-raw` or `doctor`)").into())
+        }
+    }
+}
+
+/// Provider for the Arbitrum endpoint at `ARB_URL`.
+pub fn provider() -> Result<Provider<Http>, Box<dyn std::error::Error>> {
+    let rpc_url = env::var("ARB_URL").map_err(|_| "ARB_URL must be set")?;
+    Ok(Provider::<Http>::try_from(rpc_url)?)
+}
+
+fn contract_address() -> Result<Address, Box<dyn std::error::Error>> {
+    Ok(env::var("CONTRACT_ADDRESS")
+        .unwrap_or_else(|_| DEFAULT_CONTRACT_ADDRESS.to_string())
+        .parse()?)
+}
+
+async fn connect() -> Result<BtcVerifier<Client>, Box<dyn std::error::Error>> {
     let private_key = env::var("PRIVATE_KEY").expect("PRIVATE_KEY must be set");
 
-    let provider = Provider::<Http>::try_from(rpc_url)?;
     let wallet: LocalWallet = private_key.parse()?;
-    let client = SignerMiddleware::new(provider, wallet.with_chain_id(421614u64)); // Arbitrum Sepolia Chain ID: 421614
-    let client = Arc::new(client);
-
-    println!("Connected to Arbitrum Sepolia");
-    
-    // contract address will be replaced later
-    let contract_address: Address = "0xb4864bb622f3020a5d424ff2cc20738b3327f7e2".parse()?; 
-
-    // Define the function signature: hash_btc_header(string)
-    // We can use the low-level call approach or abigen. 
-    // Since it's simple, we'll use inline abigen or manually build calldata?
-    // Abigen is cleaner.
-    
-    abigen!(
-        BtcVerifier,
-        r#"[
-            function hashBtcHeader(string memory header_hex) public view returns (string memory)
-        ]"#
-    );
-
-    let contract = BtcVerifier::new(contract_address, client);
-    
+    let client = signer(provider()?, wallet).await?;
+    let chain_id = client.signer().chain_id();
+    match doctor::network_name(chain_id) {
+        Some(name) => println!("Connected to {name} ({chain_id})"),
+        None => println!("Connected to chain {chain_id}"),
+    }
+
+    Ok(BtcVerifier::new(contract_address()?, Arc::new(client)))
+}
+
+/// Signs for whichever chain `provider` is on, so transactions built against
+/// Arbitrum One or Nova aren't rejected for carrying Sepolia's chain id.
+async fn signer(provider: Provider<Http>, wallet: LocalWallet) -> Result<Client, Box<dyn std::error::Error>> {
+    let chain_id = provider.get_chainid().await.map_err(|err| format!("no answer from ARB_URL: {err}"))?;
+    Ok(SignerMiddleware::new(provider, wallet.with_chain_id(chain_id.as_u64())))
+}
+
+/// Read-only connection for `--offline`: transactions are built for `SENDER_ADDRESS`
+/// but never signed here.
+fn connect_offline() -> Result<BtcVerifier<Provider<Http>>, Box<dyn std::error::Error>> {
+    let sender: Address = env::var("SENDER_ADDRESS").map_err(|_| "--offline needs SENDER_ADDRESS set to the signing account")?.parse()?;
+    eprintln!("Offline: printing unsigned transactions from {sender:?}");
+    Ok(BtcVerifier::new(contract_address()?, Arc::new(provider()?.with_sender(sender))))
+}
+
+async fn hash_header<M: Middleware + 'static>(contract: BtcVerifier<M>, offline: bool) -> Result<(), Box<dyn std::error::Error>> {
     let header_hex = "0200000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c"; // Genesis block header? Or just checking dummy input.
     // The example in lib.rs uses "68656c6c6f" -> "hello"
     // Use the header_hex provided by the user
     println!("Broadcasting transaction for hash_btc_header...");
-    println!("Input: {}", header_hex);
+    println!("Input: {header_hex}");
 
     // .send() broadcasts the transaction. await returns a PendingTransaction.
     // .await again waits for the receipt.
     let call = contract.hash_btc_header(header_hex.to_string());
-    let pending_tx = call.send().await?;
-    let receipt = pending_tx.await?.expect("Transaction failed to be included in a block");
+    let Some(receipt) = offline::submit(contract.client_ref(), call, offline).await? else {
+        return Ok(());
+    };
 
     println!("Transaction successfully broadcasted and included!");
     println!("Transaction Hash: {:?}", receipt.transaction_hash);
@@ -52,3 +124,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+/// Returns the value following `name` in `args`, e.g. `--from 100`.
+pub fn flag<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == name)
+        .and_then(|i| args.get(i + 1))
+        .map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcoin_rpc::tests::stub_node;
+
+    #[tokio::test]
+    async fn test_signer_uses_node_chain_id() {
+        // Arbitrum One answers eth_chainId with 42161.
+        let url = stub_node(|request| {
+            assert!(request.contains("eth_chainId"), "{request}");
+            (200, r#"{"jsonrpc":"2.0","id":1,"result":"0xa4b1"}"#.to_string())
+        })
+        .await;
+        let wallet: LocalWallet = "0x0123456789012345678901234567890123456789012345678901234567890123".parse().unwrap();
+        let client = signer(Provider::<Http>::try_from(url).unwrap(), wallet).await.unwrap();
+        assert_eq!(client.signer().chain_id(), 42161);
+    }
+}
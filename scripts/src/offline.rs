@@ -0,0 +1,87 @@
+//! `--offline` and `broadcast-raw --tx <hex>`, for signing on another machine.
+//!
+//! With `--offline`, write subcommands print each transaction unsigned instead of
+//! sending it: the calldata plus the filled-in request (to, data, gas, fees, nonce,
+//! chain id) as JSON. The sender is read from `SENDER_ADDRESS`, so `PRIVATE_KEY`
+//! never has to be present. Once signed elsewhere, `broadcast-raw` submits it.
+
+use ethers::prelude::*;
+use ethers::types::transaction::eip2718::TypedTransaction;
+use serde_json::json;
+use std::error::Error;
+
+use crate::{flag, provider};
+
+/// Sends `call` and waits for its receipt. Offline, prints it unsigned instead and
+/// returns `None`.
+pub async fn submit<M: Middleware + 'static, D: abi::Detokenize>(
+    client: &M,
+    call: ContractCall<M, D>,
+    offline: bool,
+) -> Result<Option<TransactionReceipt>, Box<dyn Error>> {
+    if !offline {
+        let receipt = call.send().await?.await?.ok_or("transaction was dropped before being mined")?;
+        return Ok(Some(receipt));
+    }
+
+    let mut tx = call.tx.clone();
+    client.fill_transaction(&mut tx, None).await.map_err(|err| err.to_string())?;
+    let from = *tx.from().ok_or("no sender for the unsigned transaction")?;
+    if tx.nonce().is_none() {
+        let nonce = client.get_transaction_count(from, Some(BlockNumber::Pending.into())).await.map_err(|err| err.to_string())?;
+        tx.set_nonce(nonce);
+    }
+    if tx.chain_id().is_none() {
+        tx.set_chain_id(client.get_chainid().await.map_err(|err| err.to_string())?.as_u64());
+    }
+    println!("{}", serde_json::to_string_pretty(&unsigned_json(&tx))?);
+    Ok(None)
+}
+
+/// The JSON printed for an unsigned transaction.
+fn unsigned_json(tx: &TypedTransaction) -> serde_json::Value {
+    json!({
+        "calldata": tx.data().cloned().unwrap_or_default(),
+        "transaction": tx,
+    })
+}
+
+/// `broadcast-raw --tx <hex>`: submits a transaction signed elsewhere.
+pub async fn broadcast_raw(args: &[String]) -> Result<(), Box<dyn Error>> {
+    let raw = flag(args, "--tx").ok_or("missing --tx <hex>")?;
+    let raw = Bytes::from(hex::decode(raw.trim_start_matches("0x"))?);
+
+    let provider = provider()?;
+    let pending = provider.send_raw_transaction(raw).await?;
+    println!("Broadcast transaction {:?}", pending.tx_hash());
+    let receipt = pending.await?.ok_or("transaction was dropped before being mined")?;
+    println!("Included in block {:?}, gas used {:?}", receipt.block_number, receipt.gas_used);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BtcVerifier;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_hash_btc_header_calldata() {
+        // Building calldata never touches the endpoint.
+        let provider = Provider::<Http>::try_from("http://127.0.0.1:1").unwrap();
+        let contract = BtcVerifier::new(Address::zero(), Arc::new(provider));
+        let call = contract.hash_btc_header("68656c6c6f".to_string());
+
+        let expected = [
+            // keccak256("hashBtcHeader(string)")[..4]
+            "b6ff973f",
+            // Offset of the string, its length, then its bytes padded to 32.
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "000000000000000000000000000000000000000000000000000000000000000a",
+            "3638363536633663366600000000000000000000000000000000000000000000",
+        ]
+        .concat();
+        assert_eq!(hex::encode(call.calldata().unwrap()), expected);
+        assert_eq!(unsigned_json(&call.tx)["calldata"], format!("0x{expected}"));
+    }
+}
@@ -0,0 +1,135 @@
+//! `prove --txid <id> --height <h> [--proof <hex,...> --index <n>] [--min-confirmations <n>]`
+//!
+//! Asks the relay whether a transaction is included in the block it accepted at
+//! `--height`, via `verifyTxAtHeight`. Without `--proof`, the block's txids are
+//! fetched from the Bitcoin node at `BITCOIN_RPC_URL` and the proof is built locally.
+
+use ethers::prelude::*;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::error::Error;
+
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::{flag, revert, BtcVerifier};
+
+pub async fn run<M: Middleware + 'static>(contract: BtcVerifier<M>, args: &[String]) -> Result<(), Box<dyn Error>> {
+    let txid = flag(args, "--txid").ok_or("missing --txid <id>")?.to_lowercase();
+    let height: u32 = flag(args, "--height").ok_or("missing --height <h>")?.parse()?;
+    let min_confirmations: u32 = flag(args, "--min-confirmations").unwrap_or("1").parse()?;
+
+    let (proof, index) = match flag(args, "--proof") {
+        Some(proof) => {
+            let index = flag(args, "--index").ok_or("missing --index <n> for the given --proof")?.parse()?;
+            (parse_proof(proof), index)
+        }
+        None => {
+            let url = env::var("BITCOIN_RPC_URL").map_err(|_| "pass --proof and --index, or set BITCOIN_RPC_URL to build them")?;
+            let txids = BitcoinRpc::new(&url)?.block_txids(height).await?;
+            let index = txids
+                .iter()
+                .position(|id| *id == txid)
+                .ok_or(format!("{txid} is not in the node's block at height {height}"))?;
+            (merkle_proof(&txids, index)?, index as u32)
+        }
+    };
+    println!("Proving {txid} at height {height}, position {index}, with {} sibling hashes", proof.len());
+
+    let included = contract
+        .verify_tx_at_height(txid, height, proof, index, min_confirmations)
+        .call()
+        .await
+        .map_err(revert::explain)?;
+    if included {
+        println!("Inclusion confirmed by the relay");
+    } else {
+        println!("NOT included: the proof does not match the block's Merkle root");
+    }
+    Ok(())
+}
+
+/// Splits a comma-separated `--proof` list, allowing an empty one for single-tx blocks.
+fn parse_proof(list: &str) -> Vec<String> {
+    list.split(',').map(str::trim).filter(|hash| !hash.is_empty()).map(String::from).collect()
+}
+
+/// Builds the Merkle branch for `txids[index]`: sibling hashes from leaf to root,
+/// all display-order hex like the txids themselves.
+fn merkle_proof(txids: &[String], mut index: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut level = txids
+        .iter()
+        .map(|txid| {
+            let mut hash: [u8; 32] = hex::decode(txid)?.try_into().map_err(|_| format!("txid {txid} is not 32 bytes"))?;
+            hash.reverse();
+            Ok(hash)
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    let mut proof = Vec::new();
+    while level.len() > 1 {
+        // An odd last node is paired with itself.
+        let sibling = *level.get(index ^ 1).unwrap_or(&level[index]);
+        proof.push(display_hex(&sibling));
+        level = level.chunks(2).map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0]))).collect();
+        index /= 2;
+    }
+    Ok(proof)
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let first = Sha256::new().chain_update(left).chain_update(right).finalize();
+    Sha256::digest(first).into()
+}
+
+fn display_hex(hash: &[u8; 32]) -> String {
+    let mut display = *hash;
+    display.reverse();
+    hex::encode(display)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mainnet block 00000000b0c5a240b2a61d2e75692224efd4cbecdf6eaf4cc2cf477ca7c270e7.
+    const ROOT: &str = "4c917a410f4e899195f816081844e56aceda71c4cc4fe634aebe9437e57344bf";
+    const COINBASE: &str = "77dfc2fe598419b00641c296181a96cf16943697f573480b023b77cce82ada21";
+    const SPEND: &str = "a3b0e9e7cddbbe78270fa4182a7675ff00b92872d8df7d14265a2b1e379a9d33";
+
+    #[test]
+    fn test_merkle_proof_for_two_tx_block() {
+        let txids = [COINBASE.to_string(), SPEND.to_string()];
+        assert_eq!(merkle_proof(&txids, 1).unwrap(), [COINBASE]);
+        assert_eq!(merkle_proof(&txids, 0).unwrap(), [SPEND]);
+        assert!(merkle_proof(&txids[..1], 0).unwrap().is_empty());
+
+        let mut leaf: [u8; 32] = hex::decode(SPEND).unwrap().try_into().unwrap();
+        let mut sibling: [u8; 32] = hex::decode(COINBASE).unwrap().try_into().unwrap();
+        leaf.reverse();
+        sibling.reverse();
+        assert_eq!(display_hex(&hash_pair(&sibling, &leaf)), ROOT);
+    }
+
+    #[test]
+    fn test_parse_proof_list() {
+        assert_eq!(parse_proof(&format!("{COINBASE}, {SPEND}")), [COINBASE, SPEND]);
+        assert!(parse_proof("").is_empty());
+    }
+
+    /// Runs against a relay on a local Nitro devnode, initialized at the mainnet
+    /// genesis block: `ARB_URL=http://localhost:8547 PRIVATE_KEY=... CONTRACT_ADDRESS=...
+    /// cargo test -- --ignored devnode`.
+    #[tokio::test]
+    #[ignore = "needs a local devnode with a relay initialized at mainnet genesis"]
+    async fn test_devnode_proves_genesis_coinbase() {
+        let contract = crate::connect().await.unwrap();
+        let coinbase = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+        let included = contract.verify_tx_at_height(coinbase.into(), 0, vec![], 0, 1).call().await.unwrap();
+        assert!(included);
+        let included = contract.verify_tx_at_height(SPEND.into(), 0, vec![], 0, 1).call().await.unwrap();
+        assert!(!included);
+
+        let err = contract.verify_tx_at_height(coinbase.into(), 0, vec![], 0, u32::MAX).call().await.unwrap_err();
+        assert_eq!(revert::explain(err).to_string(), "contract reverted: InsufficientConfirmations (code 20)");
+    }
+}
@@ -0,0 +1,79 @@
+//! Names for the relay's one-byte revert codes (`VerifyError` in the contract).
+
+use ethers::prelude::*;
+
+/// Variant names indexed by code; code 0 is unused.
+const CODES: [&str; 37] = [
+    "",
+    "InvalidHex",
+    "InvalidLength",
+    "NotInitialized",
+    "InvalidAnchor",
+    "MalformedTx",
+    "UnknownNetwork",
+    "PrevHashMismatch",
+    "InsufficientPow",
+    "BadDifficulty",
+    "UnknownBlock",
+    "NegativeTarget",
+    "TargetOverflow",
+    "LengthMismatch",
+    "InvalidPublicKey",
+    "AlreadyInitialized",
+    "NotOwner",
+    "NotPendingOwner",
+    "TooLarge",
+    "AnchorUnavailable",
+    "InsufficientConfirmations",
+    "MissingWitnessReserved",
+    "Overflow",
+    "NotAtRetarget",
+    "UnsupportedScript",
+    "HashMismatch",
+    "DuplicateHeader",
+    "InvalidAddress",
+    "InvalidPruneHeight",
+    "BadSighashType",
+    "InputOutOfRange",
+    "NegativeFee",
+    "EmptyInput",
+    "TimestampTooNew",
+    "InvalidProof",
+    "ReorgPending",
+    "ChainNotEmpty",
+];
+
+/// Describes revert data returned by the relay, e.g. `UnknownBlock (code 10)`.
+pub fn describe(data: &[u8]) -> String {
+    match data {
+        [code] => match CODES.get(*code as usize) {
+            Some(name) if !name.is_empty() => format!("{name} (code {code})"),
+            _ => format!("unknown error code {code}"),
+        },
+        [] => "reverted without data".to_string(),
+        _ => format!("unrecognized revert data 0x{}", hex::encode(data)),
+    }
+}
+
+/// Turns a failed contract call into a readable error, naming the revert code if there is one.
+pub fn explain<M: Middleware + 'static>(err: ContractError<M>) -> Box<dyn std::error::Error> {
+    match err.as_revert() {
+        Some(data) => format!("contract reverted: {}", describe(data)).into(),
+        None => err.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_revert_codes() {
+        assert_eq!(describe(&[10]), "UnknownBlock (code 10)");
+        assert_eq!(describe(&[20]), "InsufficientConfirmations (code 20)");
+        assert_eq!(describe(&[0]), "unknown error code 0");
+        assert_eq!(describe(&[200]), "unknown error code 200");
+        assert_eq!(describe(&[]), "reverted without data");
+        assert_eq!(describe(&[1, 2]), "unrecognized revert data 0x0102");
+    }
+}
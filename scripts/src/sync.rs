@@ -0,0 +1,113 @@
+//! `sync --from <height> (--headers-file <path> | --to <height>) [--gas-cap <gas>] [--offline]`
+//!
+//! Streams headers into the relay with `submitHeaders`. They come either from a
+//! file holding one hex header per line, the first at height `--from`, or straight
+//! from the Bitcoin node at `BITCOIN_RPC_URL` for heights `--from..=--to`. Progress
+//! is tracked on chain by the contract's sync cursor, so a crashed job can simply
+//! be rerun. With `--offline`, only the next batch is printed, unsigned.
+
+use ethers::prelude::*;
+use std::env;
+use std::error::Error;
+use std::fs;
+
+use crate::bitcoin_rpc::BitcoinRpc;
+use crate::{flag, offline, BtcVerifier};
+
+/// Largest batch tried before gas estimation narrows it down; the contract
+/// refuses more than this many headers per call.
+const MAX_BATCH: usize = 64;
+
+/// Default per-transaction gas cap, comfortably below Arbitrum's block gas limit.
+const DEFAULT_GAS_CAP: u64 = 20_000_000;
+
+pub async fn run<M: Middleware + 'static>(contract: BtcVerifier<M>, args: &[String], offline: bool) -> Result<(), Box<dyn Error>> {
+    let from: u32 = flag(args, "--from").ok_or("missing --from <height>")?.parse()?;
+    let gas_cap = match flag(args, "--gas-cap") {
+        Some(cap) => U256::from_dec_str(cap)?,
+        None => U256::from(DEFAULT_GAS_CAP),
+    };
+
+    let cursor = contract.sync_cursor().call().await?;
+    let (from, headers) = match flag(args, "--headers-file") {
+        Some(path) => (from, read_headers(&fs::read_to_string(path)?)),
+        None => {
+            let url = env::var("BITCOIN_RPC_URL").map_err(|_| "pass --headers-file <path> or set BITCOIN_RPC_URL")?;
+            let to: u32 = flag(args, "--to").ok_or("missing --to <height> for syncing from BITCOIN_RPC_URL")?.parse()?;
+            // Only fetch what the relay is still missing.
+            let from = from.max(cursor + 1);
+            (from, BitcoinRpc::new(&url)?.headers(from, to).await?)
+        }
+    };
+    let mut next = resume_index(from, cursor);
+    println!("Relay cursor at height {cursor}; {} of {} headers left to submit", headers.len().saturating_sub(next), headers.len());
+
+    while next < headers.len() {
+        // Halve the batch until its estimated gas fits under the cap.
+        let mut size = (headers.len() - next).min(MAX_BATCH);
+        let (call, gas) = loop {
+            let call = contract.submit_headers(headers[next..next + size].to_vec());
+            let gas = call.estimate_gas().await?;
+            if gas <= gas_cap {
+                break (call, gas);
+            }
+            if size == 1 {
+                return Err(format!("a single header needs {gas} gas, above the cap of {gas_cap}").into());
+            }
+            size /= 2;
+        };
+
+        let Some(receipt) = offline::submit(contract.client_ref(), call.gas(gas), offline).await? else {
+            // Later batches can't be estimated until this one lands, so stop here.
+            println!("Printed the batch for heights {}..={}; broadcast it, then rerun to continue", from as usize + next, from as usize + next + size - 1);
+            return Ok(());
+        };
+        next += size;
+
+        println!(
+            "Synced through height {} ({} headers, tx {:?}, gas {:?})",
+            from as usize + next - 1,
+            size,
+            receipt.transaction_hash,
+            receipt.gas_used
+        );
+    }
+
+    println!("Sync complete");
+    Ok(())
+}
+
+/// Parses a headers file: one hex header per line, blank lines ignored.
+fn read_headers(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Index of the first header in a file starting at `from` that the relay,
+/// whose cursor is at `cursor`, hasn't accepted yet.
+fn resume_index(from: u32, cursor: u32) -> usize {
+    (cursor as usize + 1).saturating_sub(from as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resume_after_interruption() {
+        let headers = read_headers("aa\n\nbb\ncc\ndd\n");
+        assert_eq!(headers.len(), 4);
+
+        // Fresh relay checkpointed just below the file: start at the top.
+        assert_eq!(resume_index(101, 100), 0);
+        // A batch covering heights 101..=102 landed before the crash.
+        assert_eq!(resume_index(101, 102), 2);
+        assert_eq!(headers[resume_index(101, 102)..], ["cc", "dd"]);
+        // Everything already synced.
+        assert_eq!(resume_index(101, 104), headers.len());
+    }
+}
@@ -0,0 +1,137 @@
+//! Decoding of Bitcoin addresses into the scripts they pay to.
+//!
+//! Base58Check covers legacy P2PKH and P2SH addresses, BIP173 bech32 covers
+//! segwit v0 (P2WPKH, P2WSH) and BIP350 bech32m covers v1+ (P2TR). Mainnet,
+//! testnet/signet and regtest prefixes are all accepted.
+
+use alloc::vec::Vec;
+
+use crate::error::VerifyError;
+use crate::hash::hash256;
+use crate::script::{p2pkh, OP_0, OP_1, OP_EQUAL, OP_HASH160};
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+
+/// Returns the scriptPubKey `address` pays to, or `InvalidAddress`.
+pub fn script_pubkey(address: &str) -> Result<Vec<u8>, VerifyError> {
+    match segwit_hrp(address) {
+        Some(hrp_len) => segwit_script(address, hrp_len),
+        None => base58_script(address),
+    }
+}
+
+/// Length of the human-readable part if `address` looks like a segwit address.
+fn segwit_hrp(address: &str) -> Option<usize> {
+    let lower = address.to_ascii_lowercase();
+    ["bcrt1", "bc1", "tb1"].iter().find(|prefix| lower.starts_with(*prefix)).map(|prefix| prefix.len() - 1)
+}
+
+fn base58_script(address: &str) -> Result<Vec<u8>, VerifyError> {
+    let decoded = base58_decode(address).ok_or(VerifyError::InvalidAddress)?;
+    let [version, payload @ .., c0, c1, c2, c3] = decoded.as_slice() else {
+        return Err(VerifyError::InvalidAddress);
+    };
+    if decoded.len() != 25 || hash256(&decoded[..21])[..4] != [*c0, *c1, *c2, *c3] {
+        return Err(VerifyError::InvalidAddress);
+    }
+    let hash: &[u8; 20] = payload.try_into().map_err(|_| VerifyError::InvalidAddress)?;
+    match version {
+        // P2PKH on mainnet and on testnet/signet/regtest.
+        0x00 | 0x6f => Ok(p2pkh(hash)),
+        // P2SH on the same networks.
+        0x05 | 0xc4 => {
+            let mut script = Vec::with_capacity(23);
+            script.extend_from_slice(&[OP_HASH160, 20]);
+            script.extend_from_slice(hash);
+            script.push(OP_EQUAL);
+            Ok(script)
+        }
+        _ => Err(VerifyError::InvalidAddress),
+    }
+}
+
+/// Decodes base58, keeping leading `1`s as zero bytes.
+fn base58_decode(input: &str) -> Option<Vec<u8>> {
+    // Big-endian base-256 number, grown as digits are folded in.
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in input.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = input.bytes().take_while(|&c| c == b'1').count();
+    let mut decoded = alloc::vec![0u8; zeros];
+    decoded.extend_from_slice(&bytes);
+    Some(decoded)
+}
+
+fn segwit_script(address: &str, hrp_len: usize) -> Result<Vec<u8>, VerifyError> {
+    // Mixed case is invalid; otherwise bech32 is case-insensitive.
+    if address.len() > 90 || (address.bytes().any(|c| c.is_ascii_lowercase()) && address.bytes().any(|c| c.is_ascii_uppercase())) {
+        return Err(VerifyError::InvalidAddress);
+    }
+    let lower = address.to_ascii_lowercase();
+    let (hrp, data) = (&lower.as_bytes()[..hrp_len], &lower.as_bytes()[hrp_len + 1..]);
+    let values = data
+        .iter()
+        .map(|c| BECH32_CHARSET.iter().position(|a| a == c).map(|v| v as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or(VerifyError::InvalidAddress)?;
+    if values.len() < 7 {
+        return Err(VerifyError::InvalidAddress);
+    }
+
+    let (payload, _checksum) = values.split_at(values.len() - 6);
+    let version = payload[0];
+    let expected = if version == 0 { BECH32_CONST } else { BECH32M_CONST };
+    if polymod(hrp, &values) != expected || version > 16 {
+        return Err(VerifyError::InvalidAddress);
+    }
+    let program = convert_bits(&payload[1..]).ok_or(VerifyError::InvalidAddress)?;
+    if !(2..=40).contains(&program.len()) || (version == 0 && program.len() != 20 && program.len() != 32) {
+        return Err(VerifyError::InvalidAddress);
+    }
+
+    let mut script = Vec::with_capacity(program.len() + 2);
+    script.push(if version == 0 { OP_0 } else { OP_1 + version - 1 });
+    script.push(program.len() as u8);
+    script.extend_from_slice(&program);
+    Ok(script)
+}
+
+/// The BIP173 checksum polymod over the expanded `hrp` and `values`.
+fn polymod(hrp: &[u8], values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+    let expanded = hrp.iter().map(|c| c >> 5).chain([0]).chain(hrp.iter().map(|c| c & 31)).chain(values.iter().copied());
+    expanded.fold(1u32, |chk, value| {
+        let top = chk >> 25;
+        let chk = ((chk & 0x01ff_ffff) << 5) ^ value as u32;
+        GEN.iter().enumerate().filter(|(i, _)| (top >> i) & 1 == 1).fold(chk, |chk, (_, g)| chk ^ g)
+    })
+}
+
+/// Regroups 5-bit values into bytes, rejecting more than 4 bits of padding or
+/// padding that isn't zero.
+fn convert_bits(values: &[u8]) -> Option<Vec<u8>> {
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let mut out = Vec::with_capacity(values.len() * 5 / 8);
+    for &value in values {
+        acc = ((acc << 5) | value as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    (bits < 5 && acc & ((1 << bits) - 1) == 0).then_some(out)
+}
@@ -0,0 +1,48 @@
+//! Signature verification and public key encoding on secp256k1.
+
+use alloc::vec::Vec;
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use k256::ecdsa::{Signature as EcdsaSignature, VerifyingKey};
+use k256::schnorr::{Signature as SchnorrSignature, VerifyingKey as XOnlyKey};
+
+use crate::error::VerifyError;
+
+/// Verifies a BIP340 Schnorr signature over a 32-byte message (e.g. a Taproot
+/// sighash) against an x-only public key.
+///
+/// Returns false for signatures that don't verify, including ones whose `r` or
+/// `s` are out of range, and for keys whose `lift_x` fails, as BIP340 specifies.
+pub fn verify_schnorr(msg: &[u8; 32], sig: &[u8; 64], pubkey: &[u8; 32]) -> bool {
+    let (Ok(key), Ok(sig)) = (XOnlyKey::from_bytes(pubkey), SchnorrSignature::try_from(sig.as_slice())) else {
+        return false;
+    };
+    key.verify_raw(msg, &sig).is_ok()
+}
+
+/// Verifies a DER-encoded ECDSA signature (without a sighash byte) over a 32-byte
+/// digest against a compressed or uncompressed SEC1 public key.
+///
+/// High-S signatures are normalized first, as consensus accepts them. Returns
+/// `Ok(false)` for signatures that don't verify or aren't strict DER, and an error
+/// if the key isn't on the curve.
+pub fn verify_ecdsa(digest: &[u8; 32], der_sig: &[u8], pubkey: &[u8]) -> Result<bool, VerifyError> {
+    let key = VerifyingKey::from_sec1_bytes(pubkey).map_err(|_| VerifyError::InvalidPublicKey)?;
+    let Ok(sig) = EcdsaSignature::from_der(der_sig) else {
+        return Ok(false);
+    };
+    let sig = sig.normalize_s().unwrap_or(sig);
+    Ok(key.verify_prehash(digest, &sig).is_ok())
+}
+
+/// Re-encodes a 33-byte compressed or 65-byte uncompressed SEC1 public key in the
+/// requested form, recovering `y` from its parity when decompressing.
+///
+/// Fails with `InvalidLength` for other sizes and `InvalidPublicKey` if the key
+/// isn't on the curve.
+pub fn normalize_pubkey(pubkey: &[u8], compressed: bool) -> Result<Vec<u8>, VerifyError> {
+    if pubkey.len() != 33 && pubkey.len() != 65 {
+        return Err(VerifyError::InvalidLength);
+    }
+    let key = VerifyingKey::from_sec1_bytes(pubkey).map_err(|_| VerifyError::InvalidPublicKey)?;
+    Ok(key.to_encoded_point(compressed).as_bytes().to_vec())
+}
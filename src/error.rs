@@ -0,0 +1,91 @@
+//! Revert codes shared by every contract method.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Reasons a call can fail.
+///
+/// Each variant is returned to the caller as a single byte, which keeps
+/// reverts cheap while still letting off-chain tooling tell failures apart.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// An argument was not valid hex.
+    InvalidHex = 1,
+    /// An argument decoded to the wrong number of bytes.
+    InvalidLength = 2,
+    /// The relay has not been seeded with a checkpoint yet.
+    NotInitialized = 3,
+    /// The supplied retarget anchor does not fit the checkpoint.
+    InvalidAnchor = 4,
+    /// A transaction could not be parsed.
+    MalformedTx = 5,
+    /// The network id passed to `initialize` is not one the relay knows.
+    UnknownNetwork = 6,
+    /// The header's `prev_block` is not the block it was submitted on top of.
+    PrevHashMismatch = 7,
+    /// The header's hash is above its target, or the target is easier than the pow limit.
+    InsufficientPow = 8,
+    /// The header's `bits` differ from what the difficulty rules require.
+    BadDifficulty = 9,
+    /// No header is stored for the requested block.
+    UnknownBlock = 10,
+    /// Compact `bits` with the sign bit set on a nonzero mantissa.
+    NegativeTarget = 11,
+    /// Compact `bits` whose exponent pushes the mantissa past 256 bits.
+    TargetOverflow = 12,
+    /// Parallel array arguments have different lengths.
+    LengthMismatch = 13,
+    /// A public key is not a valid secp256k1 point.
+    InvalidPublicKey = 14,
+    /// `initialize` has already been called.
+    AlreadyInitialized = 15,
+    /// The caller is not the owner.
+    NotOwner = 16,
+    /// The caller is not the pending owner.
+    NotPendingOwner = 17,
+    /// An input or walk is longer than the caps in `limits` allow.
+    TooLarge = 18,
+    /// A header starts an epoch, but the relay has no start recorded for its parent's.
+    AnchorUnavailable = 19,
+    /// The block is not yet buried under the requested number of confirmations.
+    InsufficientConfirmations = 20,
+    /// A coinbase's witness is not the single 32-byte reserved value BIP141 requires.
+    MissingWitnessReserved = 21,
+    /// Target, work or chainwork arithmetic would not fit in 256 bits.
+    Overflow = 22,
+    /// The tip is not the last block of a difficulty epoch.
+    NotAtRetarget = 23,
+    /// A script is not one of the templates the relay can evaluate.
+    UnsupportedScript = 24,
+    /// A supplied header doesn't hash to the block hash it was given for.
+    HashMismatch = 25,
+    /// The header is already stored.
+    DuplicateHeader = 26,
+    /// The string isn't a valid Base58Check or bech32 address.
+    InvalidAddress = 27,
+    /// `prune_headers` was asked to delete the tip or blocks above it.
+    InvalidPruneHeight = 28,
+    /// The sighash type isn't `ALL`, `NONE` or `SINGLE`, optionally with `ANYONECANPAY`.
+    BadSighashType = 29,
+    /// The input index is past the transaction's last input.
+    InputOutOfRange = 30,
+    /// A transaction's outputs spend more than its inputs provide.
+    NegativeFee = 31,
+    /// A list argument was empty where at least one item is required.
+    EmptyInput = 32,
+    /// The header's timestamp is further ahead of the Arbitrum block's than `max_future_drift` allows.
+    TimestampTooNew = 33,
+    /// A Merkle proof that had to hold for the call to succeed didn't.
+    InvalidProof = 34,
+    /// A reorg hasn't finished indexing its branch by height; call `continue_reorg`.
+    ReorgPending = 35,
+    /// `import_state` was called on a relay storing more than a single header.
+    ChainNotEmpty = 36,
+}
+
+impl From<VerifyError> for Vec<u8> {
+    fn from(err: VerifyError) -> Self {
+        vec![err as u8]
+    }
+}
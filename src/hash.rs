@@ -0,0 +1,15 @@
+//! Hash functions used by the Bitcoin protocol.
+
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256};
+
+/// Double SHA-256 ("Hash256"), used for block hashes and txids.
+pub fn hash256(data: &[u8]) -> [u8; 32] {
+    let first = Sha256::digest(data);
+    Sha256::digest(first).into()
+}
+
+/// RIPEMD-160 of SHA-256 ("Hash160"), used for public key and script hashes.
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
@@ -0,0 +1,83 @@
+//! Parsing and serialization of the 80-byte Bitcoin block header.
+
+use crate::error::VerifyError;
+
+/// The fields of a block header, as laid out on the wire.
+///
+/// Hashes are kept in internal (little-endian) byte order, exactly as they
+/// appear in the serialized header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub prev_block: [u8; 32],
+    pub merkle_root: [u8; 32],
+    pub timestamp: u32,
+    pub bits: u32,
+    pub nonce: u32,
+}
+
+impl Header {
+    /// Size of a serialized header in bytes.
+    pub const SIZE: usize = 80;
+
+    /// Parses a serialized header, rejecting anything that isn't exactly 80 bytes.
+    pub fn parse(bytes: &[u8]) -> Result<Self, VerifyError> {
+        let bytes: &[u8; Self::SIZE] = bytes.try_into().map_err(|_| VerifyError::InvalidLength)?;
+        Ok(Self::from_bytes(bytes))
+    }
+
+    /// Splits a serialized header into its fields.
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        let mut prev_block = [0u8; 32];
+        prev_block.copy_from_slice(&bytes[4..36]);
+        let mut merkle_root = [0u8; 32];
+        merkle_root.copy_from_slice(&bytes[36..68]);
+
+        Self {
+            version: read_u32_le(bytes, 0),
+            prev_block,
+            merkle_root,
+            timestamp: read_u32_le(bytes, 68),
+            bits: read_u32_le(bytes, 72),
+            nonce: read_u32_le(bytes, 76),
+        }
+    }
+
+    /// Serializes the header back into the exact bytes `from_bytes` reads.
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0u8; Self::SIZE];
+        bytes[0..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..36].copy_from_slice(&self.prev_block);
+        bytes[36..68].copy_from_slice(&self.merkle_root);
+        bytes[68..72].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[72..76].copy_from_slice(&self.bits.to_le_bytes());
+        bytes[76..80].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes
+    }
+}
+
+impl From<&[u8; Header::SIZE]> for Header {
+    fn from(bytes: &[u8; Header::SIZE]) -> Self {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Header {
+    type Error = VerifyError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::parse(bytes)
+    }
+}
+
+impl From<&Header> for [u8; Header::SIZE] {
+    fn from(header: &Header) -> Self {
+        header.to_bytes()
+    }
+}
+
+fn read_u32_le(bytes: &[u8], offset: usize) -> u32 {
+    let mut word = [0u8; 4];
+    word.copy_from_slice(&bytes[offset..offset + 4]);
+    u32::from_le_bytes(word)
+}
@@ -1,18 +1,135 @@
-#![cfg_attr(not(feature = "export-abi"), no_main)]
+#![cfg_attr(not(any(test, feature = "export-abi")), no_main)]
 extern crate alloc;
 
-use stylus_sdk::{prelude::*};
+pub mod address;
+pub mod ecc;
+pub mod error;
+pub mod hash;
+pub mod header;
+pub mod limits;
+pub mod merkle;
+pub mod params;
+pub mod pow;
+#[cfg(feature = "relay")]
+pub mod relay;
+pub mod script;
+pub mod sighash;
+pub mod signet;
+pub mod tx;
+
+use alloy_sol_types::{sol, Revert, SolError};
+use stylus_sdk::{
+    alloy_primitives::{FixedBytes, U256},
+    prelude::*,
+};
+#[cfg(feature = "relay")]
+use stylus_sdk::storage::{
+    StorageAddress, StorageBool, StorageBytes, StorageFixedBytes, StorageMap, StorageU256, StorageU32, StorageU64, StorageU8,
+};
 use alloc::string::String;
 use alloc::vec::Vec;
 use sha2::{Sha256, Digest};
 
+pub use error::VerifyError;
+#[cfg(feature = "relay")]
+pub use relay::IRelay;
+// `implements(IRelay)` names the struct `#[public]` generates for the trait's ABI.
+#[cfg(all(feature = "relay", feature = "export-abi"))]
+use relay::IRelayStylusAbiStruct;
+use header::Header;
+use limits::{MAX_BATCH, MAX_PROOF_LEN};
+use tx::Transaction;
+
+sol! {
+    /// Emitted when a pending owner accepts ownership.
+    event OwnershipTransferred(address indexed previousOwner, address indexed newOwner);
+
+    /// Emitted when a heavier fork replaces the best chain. Hashes are in internal
+    /// byte order; `depth` is the number of blocks rewound from the old tip.
+    event ReorgDetected(uint32 oldHeight, bytes32 oldTip, uint32 newHeight, bytes32 newTip, uint256 depth);
+}
+
+/// Chain state carried between deployments:
+/// `(tip_height, tip_hash, chainwork, anchor_height, anchor_timestamp)`, with the
+/// tip hash in display order and its chainwork as 32-byte big-endian hex.
+pub type ChainState = (u32, String, String, u32, u32);
+
+/// A header as returned over the ABI:
+/// `(version, prev_block, merkle_root, timestamp, bits, nonce)`, hashes in display order.
+pub type HeaderFields = (u32, String, String, u32, u32, u32);
+
 // #[storage] defines the persistent storage layout of the contract.
-// Even if unused, it's required for the entrypoint struct.
+#[cfg(feature = "relay")]
 #[storage]
 #[entrypoint] // #[entrypoint] marks this struct as the main entry point to the contract.
-pub struct BtcVerifier;
+pub struct BtcVerifier {
+    /// Set by `initialize`, which may only run once.
+    initialized: StorageBool,
+    /// Account allowed to perform administrative actions; the caller of `initialize`.
+    owner: StorageAddress,
+    /// Proposed owner awaiting `accept_ownership`, or zero.
+    pending_owner: StorageAddress,
+    /// The `Network` discriminant the relay validates against.
+    network: StorageU8,
+    /// Easiest target a header may have; the network's own limit unless overridden at `initialize`.
+    pow_limit: StorageU256,
+    /// Height of the best header the relay knows about.
+    tip_height: StorageU32,
+    /// Hash (internal byte order) of the best header.
+    tip_hash: StorageFixedBytes<32>,
+    /// Raw 80-byte headers, keyed by block hash.
+    headers: StorageMap<FixedBytes<32>, StorageBytes>,
+    /// Cumulative work from the checkpoint up to and including each block, keyed by hash.
+    chainwork: StorageMap<FixedBytes<32>, StorageU256>,
+    /// Height of every stored header, keyed by hash.
+    heights: StorageMap<FixedBytes<32>, StorageU32>,
+    /// Hash of the best chain's header at each height, rewritten on reorgs.
+    canonical_hash_at: StorageMap<u32, StorageFixedBytes<32>>,
+    /// Height of the first block in the tip's difficulty epoch.
+    anchor_height: StorageU32,
+    /// Timestamp of the block at `anchor_height`; the start of the retarget timespan.
+    anchor_timestamp: StorageU32,
+    /// Height the tip was last moved to, so interrupted syncs can resume.
+    sync_cursor: StorageU32,
+    /// Lowest height whose header is still stored; raised by `prune_headers`.
+    lowest_height: StorageU32,
+    /// Number of headers stored, on any branch.
+    header_count: StorageU32,
+    /// Seconds a header's timestamp may be ahead of the Arbitrum block's.
+    max_future_drift: StorageU32,
+    /// Arbitrum block timestamp at which the last header was accepted.
+    last_submit_ts: StorageU64,
+    /// Blocks per difficulty epoch; `RETARGET_INTERVAL` unless overridden at `initialize`.
+    retarget_interval: StorageU32,
+    /// Expected duration of an epoch; `TARGET_TIMESPAN` unless overridden at `initialize`.
+    target_timespan: StorageU32,
+    /// Timestamp of the first block in each stored block's difficulty epoch, keyed by hash.
+    epoch_start: StorageMap<FixedBytes<32>, StorageU32>,
+    /// Next block of a reorg's branch whose height `canonical_hash_at` still maps
+    /// to the old chain, or zero when no reorg is pending.
+    reorg_cursor: StorageFixedBytes<32>,
+    /// Tip the pending reorg replaced, for its `ReorgDetected` event.
+    reorg_old_tip: StorageFixedBytes<32>,
+    /// Highest height above the tip that may still map to the old chain.
+    reorg_stale_height: StorageU32,
+}
+
+/// Without the `relay` feature the contract keeps no state.
+#[cfg(not(feature = "relay"))]
+#[storage]
+#[entrypoint]
+pub struct BtcVerifier {}
+
+#[cfg(not(feature = "relay"))]
+impl BtcVerifier {
+    /// With no relay to configure, epochs are always Bitcoin's.
+    fn retarget_interval(&self) -> u32 {
+        params::RETARGET_INTERVAL
+    }
+}
 
 #[public] // #[public] makes methods in this impl block callable from other contracts/EOAs.
+#[cfg_attr(feature = "relay", implements(IRelay))]
 impl BtcVerifier {
     /// verifying a Bitcoin block header often requires double-SHA256 (Hash256).
     /// This function takes a hex string, decodes it, hashes it twice, and returns the result.
@@ -35,11 +152,540 @@ impl BtcVerifier {
         // 4. Return the double-hashed result as a hex string.
         Ok(hex::encode(hash2))
     }
+
+    /// Byte-oriented variant of `hash_btc_header` for on-chain callers: takes the raw
+    /// 80-byte header and returns its double-SHA256 (internal byte order) without
+    /// any hex encoding or decoding.
+    pub fn hash_btc_header_bytes(&self, header: Vec<u8>) -> Result<FixedBytes<32>, Vec<u8>> {
+        if header.len() != Header::SIZE {
+            return Err(VerifyError::InvalidLength.into());
+        }
+        Ok(FixedBytes::from(hash::hash256(&header)))
+    }
+
+    /// Double-SHA256 of arbitrary bytes, in internal byte order: the general form of
+    /// `hash_btc_header_bytes` for transactions, Merkle nodes and other Bitcoin data.
+    pub fn hash256_bytes(&self, data: Vec<u8>) -> FixedBytes<32> {
+        FixedBytes::from(hash::hash256(&data))
+    }
+
+    /// Hashes each 80-byte header like `hash_btc_header`, in order. At most
+    /// `MAX_BATCH` headers are accepted.
+    pub fn hash_btc_headers(&self, headers: Vec<String>) -> Result<Vec<String>, Vec<u8>> {
+        if headers.is_empty() {
+            return Err(VerifyError::EmptyInput.into());
+        }
+        if headers.len() > MAX_BATCH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        headers
+            .iter()
+            .map(|header_hex| {
+                let bytes = decode_hex(header_hex)?;
+                if bytes.len() != Header::SIZE {
+                    return Err(VerifyError::InvalidLength.into());
+                }
+                Ok(hex::encode(hash::hash256(&bytes)))
+            })
+            .collect()
+    }
+
+    /// Verifies a BIP340 Schnorr signature: a 32-byte message, 64-byte signature
+    /// and 32-byte x-only public key, all hex.
+    pub fn verify_schnorr(&self, msg_hex: String, sig_hex: String, pubkey_hex: String) -> Result<bool, Vec<u8>> {
+        Ok(ecc::verify_schnorr(
+            &decode_array(&msg_hex)?,
+            &decode_array(&sig_hex)?,
+            &decode_array(&pubkey_hex)?,
+        ))
+    }
+
+    /// Verifies many `(message, signature, public key)` triples, e.g. every
+    /// Taproot input of a transaction. Returns true only if all of them verify.
+    /// At least one and at most `MAX_BATCH` triples are accepted.
+    pub fn verify_schnorr_batch(
+        &self,
+        msgs_hex: Vec<String>,
+        sigs_hex: Vec<String>,
+        pubkeys_hex: Vec<String>,
+    ) -> Result<bool, Vec<u8>> {
+        if msgs_hex.len() != sigs_hex.len() || msgs_hex.len() != pubkeys_hex.len() {
+            return Err(VerifyError::LengthMismatch.into());
+        }
+        // An empty batch would verify vacuously.
+        if msgs_hex.is_empty() {
+            return Err(VerifyError::EmptyInput.into());
+        }
+        if msgs_hex.len() > MAX_BATCH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        // k256 has no batch verifier, so check each triple and stop at the first failure.
+        for ((msg, sig), pubkey) in msgs_hex.iter().zip(&sigs_hex).zip(&pubkeys_hex) {
+            if !ecc::verify_schnorr(&decode_array(msg)?, &decode_array(sig)?, &decode_array(pubkey)?) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Converts a 33-byte compressed or 65-byte uncompressed public key to the
+    /// requested form, so keys can be hashed into addresses consistently.
+    pub fn normalize_pubkey(&self, pubkey_hex: String, want_compressed: bool) -> Result<String, Vec<u8>> {
+        Ok(hex::encode(ecc::normalize_pubkey(&decode_hex(&pubkey_hex)?, want_compressed)?))
+    }
+
+    /// Splits a header into `(version, prev_block, merkle_root, timestamp, bits, nonce)`.
+    /// Both hashes are returned as display-order hex.
+    pub fn parse_header(&self, header_hex: String) -> Result<HeaderFields, Vec<u8>> {
+        let header = Header::parse(&decode_hex(&header_hex)?)?;
+        Ok(header_fields(&header))
+    }
+
+    /// Checks that a header's hash is at or below the target encoded in its own `bits`.
+    pub fn verify_pow(&self, header_hex: String) -> Result<bool, Vec<u8>> {
+        let bytes = decode_hex(&header_hex)?;
+        let header = Header::parse(&bytes)?;
+        Ok(pow::hash_meets_target(&hash::hash256(&bytes), pow::bits_to_target(header.bits)?))
+    }
+
+    /// Checks a header's hash against `target_hex`, a 32-byte big-endian target
+    /// supplied by the caller, ignoring the header's own `bits`.
+    pub fn verify_pow_against_target(&self, header_hex: String, target_hex: String) -> Result<bool, Vec<u8>> {
+        let bytes = decode_hex(&header_hex)?;
+        Header::parse(&bytes)?;
+        let target = U256::from_be_bytes(decode_array::<32>(&target_hex)?);
+        Ok(pow::hash_meets_target(&hash::hash256(&bytes), target))
+    }
+
+    /// Reverts unless `header_hex` is a header meeting the target in its own `bits`.
+    /// Unlike the other methods it fails with a Solidity `Error(string)` such as
+    /// `"BTC: pow check failed"`, which ethers and hardhat decode on their own.
+    pub fn require_valid_header(&self, header_hex: String) -> Result<(), Vec<u8>> {
+        let revert = |reason: &str| Revert::from(reason).abi_encode();
+        let bytes = decode_hex(&header_hex).map_err(|_| revert("BTC: header is not valid hex"))?;
+        let header = Header::parse(&bytes).map_err(|_| revert("BTC: header must be 80 bytes"))?;
+        let target = pow::bits_to_target(header.bits).map_err(|_| revert("BTC: invalid bits"))?;
+        if !pow::hash_meets_target(&hash::hash256(&bytes), target) {
+            return Err(revert("BTC: pow check failed"));
+        }
+        Ok(())
+    }
+
+    /// Checks a chain of headers packed back to back as 80-byte records: each must
+    /// meet the target in its own `bits` and build on the one before it. Returns the
+    /// last header's hash in display order.
+    ///
+    /// Reverts with `InvalidLength` unless the length is a multiple of 80, and with
+    /// `InsufficientPow` or `PrevHashMismatch` at the first header that fails. At most
+    /// `MAX_BATCH` headers are accepted.
+    pub fn verify_header_chain_packed(&self, headers: Vec<u8>) -> Result<String, Vec<u8>> {
+        if headers.is_empty() {
+            return Err(VerifyError::EmptyInput.into());
+        }
+        if headers.len() % Header::SIZE != 0 {
+            return Err(VerifyError::InvalidLength.into());
+        }
+        if headers.len() / Header::SIZE > MAX_BATCH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        let mut prev_hash: Option<[u8; 32]> = None;
+        for bytes in headers.chunks_exact(Header::SIZE) {
+            let header = Header::parse(bytes)?;
+            let hash = hash::hash256(bytes);
+            if prev_hash.is_some_and(|prev| prev != header.prev_block) {
+                return Err(VerifyError::PrevHashMismatch.into());
+            }
+            if !pow::hash_meets_target(&hash, pow::bits_to_target(header.bits)?) {
+                return Err(VerifyError::InsufficientPow.into());
+            }
+            prev_hash = Some(hash);
+        }
+        Ok(encode_display_hash(&prev_hash.unwrap_or_default()))
+    }
+
+    /// Computes the Merkle root of a block's txids.
+    /// Txids and the returned root are hex in display (big-endian) order; a single
+    /// txid is its own root.
+    pub fn merkle_root_from_txids(&self, txids: Vec<String>) -> Result<String, Vec<u8>> {
+        let leaves = decode_display_hashes(&txids)?;
+        let root = merkle::merkle_root(&leaves).ok_or(VerifyError::EmptyInput)?;
+        Ok(encode_display_hash(&root))
+    }
+
+    /// Computes the Merkle root of txids packed back to back as 32-byte records in
+    /// internal byte order, returning the root in the same order. Cheaper to call
+    /// than `merkle_root_from_txids` for large blocks.
+    pub fn merkle_root_packed(&self, txids: Vec<u8>) -> Result<FixedBytes<32>, Vec<u8>> {
+        if txids.len() % 32 != 0 {
+            return Err(VerifyError::InvalidLength.into());
+        }
+        let leaves = txids
+            .chunks_exact(32)
+            .map(|txid| {
+                let mut leaf = [0u8; 32];
+                leaf.copy_from_slice(txid);
+                leaf
+            })
+            .collect::<Vec<_>>();
+        Ok(FixedBytes::from(merkle::merkle_root(&leaves).ok_or(VerifyError::EmptyInput)?))
+    }
+
+    /// Number of sibling hashes a Merkle proof contains in a block of `tx_count`
+    /// transactions, so callers can size their proof arrays up front.
+    pub fn merkle_depth(&self, tx_count: u32) -> u32 {
+        merkle::depth(tx_count)
+    }
+
+    /// Checks that `txid` sits at position `index` in a block with `merkle_root`,
+    /// given the sibling hashes from leaf to root. All hashes are display-order hex.
+    pub fn verify_merkle_proof(
+        &self,
+        txid_hex: String,
+        merkle_root_hex: String,
+        proof: Vec<String>,
+        index: u32,
+    ) -> Result<bool, Vec<u8>> {
+        let proof = decode_proof(&proof)?;
+        let txid = decode_display_hash(&txid_hex)?;
+        let root = decode_display_hash(&merkle_root_hex)?;
+        Ok(merkle::root_from_proof(&txid, &proof, index) == Some(root))
+    }
+
+    /// Returns the Merkle root, in display order, that a proof for `leaf_hex` at
+    /// position `index` leads to, for comparing against several candidate roots.
+    /// Reverts with `InvalidLength` if `index` needs more levels than the proof has.
+    pub fn compute_merkle_root_from_proof(&self, leaf_hex: String, proof: Vec<String>, index: u32) -> Result<String, Vec<u8>> {
+        let proof = decode_proof(&proof)?;
+        let leaf = decode_display_hash(&leaf_hex)?;
+        let root = merkle::root_from_proof(&leaf, &proof, index).ok_or(VerifyError::InvalidLength)?;
+        Ok(encode_display_hash(&root))
+    }
+
+    /// Returns the hex witness reserved value of a coinbase transaction: the single
+    /// 32-byte item on its input's witness stack, which `verify_witness_commitment`
+    /// hashes together with the witness root. Reverts with `MissingWitnessReserved`
+    /// if the witness is absent or isn't exactly one 32-byte item.
+    pub fn coinbase_witness_reserved(&self, coinbase_tx_hex: String) -> Result<String, Vec<u8>> {
+        let coinbase = Transaction::parse(&decode_hex(&coinbase_tx_hex)?)?;
+        let reserved = coinbase.witness_reserved_value().ok_or(VerifyError::MissingWitnessReserved)?;
+        Ok(hex::encode(reserved))
+    }
+
+    /// Checks the BIP141 witness commitment in a coinbase against the block's wtxids
+    /// (display order). The coinbase's own entry is taken as zero, as consensus
+    /// requires, so callers may pass either zero or its txid there.
+    pub fn verify_witness_commitment(&self, coinbase_tx_hex: String, wtxids: Vec<String>) -> Result<bool, Vec<u8>> {
+        let coinbase = Transaction::parse(&decode_hex(&coinbase_tx_hex)?)?;
+        let wtxids = decode_display_hashes(&wtxids)?;
+        Ok(witness_commitment_matches(&coinbase, wtxids)?)
+    }
+
+    /// Returns the scriptPubKey, hex encoded, that a Base58Check (P2PKH, P2SH) or
+    /// bech32/bech32m (P2WPKH, P2WSH, P2TR) address pays to, for comparing against
+    /// a transaction output to confirm a payment.
+    pub fn address_to_script(&self, address: String) -> Result<String, Vec<u8>> {
+        Ok(hex::encode(address::script_pubkey(&address)?))
+    }
+
+    /// Computes the legacy signature hash for input `input_index` of `tx_hex`,
+    /// spending an output locked by `script_code_hex`. The digest is returned as
+    /// signed, not byte-reversed.
+    ///
+    /// Reverts with `InputOutOfRange` or `BadSighashType` for an invalid index or
+    /// type. `SIGHASH_SINGLE` without a matching output keeps consensus' quirk of
+    /// hashing to the value one.
+    pub fn sighash_legacy(
+        &self,
+        tx_hex: String,
+        input_index: u32,
+        script_code_hex: String,
+        sighash_type: u32,
+    ) -> Result<String, Vec<u8>> {
+        let tx = Transaction::parse(&decode_hex(&tx_hex)?)?;
+        sighash::check_type(sighash_type)?;
+        // sighash::legacy would hash a missing input to one, like Core.
+        if input_index as usize >= tx.inputs.len() {
+            return Err(VerifyError::InputOutOfRange.into());
+        }
+        let digest = sighash::legacy(&tx, input_index as usize, &decode_hex(&script_code_hex)?, sighash_type)?;
+        Ok(hex::encode(digest))
+    }
+
+    /// Computes the BIP143 signature hash for segwit v0 input `input_index` of
+    /// `tx_hex`, spending `amount` satoshis locked by `script_code_hex`, with the
+    /// same validation and output form as `sighash_legacy`.
+    pub fn sighash_segwit_v0(
+        &self,
+        tx_hex: String,
+        input_index: u32,
+        script_code_hex: String,
+        amount: u64,
+        sighash_type: u32,
+    ) -> Result<String, Vec<u8>> {
+        let tx = Transaction::parse(&decode_hex(&tx_hex)?)?;
+        sighash::check_type(sighash_type)?;
+        let digest = sighash::segwit_v0(&tx, input_index as usize, &decode_hex(&script_code_hex)?, amount, sighash_type)?;
+        Ok(hex::encode(digest))
+    }
+
+    /// Verifies the signature of a P2WPKH input: `sig_hex` is the DER signature with
+    /// its sighash byte, as in the witness, over input `input_index` of `tx_hex`
+    /// spending `amount` satoshis. The script code is the P2PKH script implied by
+    /// Hash160 of `pubkey_hex`; the caller checks that hash against the spent output.
+    pub fn verify_p2wpkh_spend(
+        &self,
+        tx_hex: String,
+        input_index: u32,
+        amount: u64,
+        pubkey_hex: String,
+        sig_hex: String,
+    ) -> Result<bool, Vec<u8>> {
+        let tx = Transaction::parse(&decode_hex(&tx_hex)?)?;
+        let pubkey = decode_hex(&pubkey_hex)?;
+        let sig = decode_hex(&sig_hex)?;
+        let Some((&sighash_type, der)) = sig.split_last() else {
+            return Ok(false);
+        };
+        sighash::check_type(sighash_type as u32)?;
+
+        let script_code = script::p2pkh(&hash::hash160(&pubkey));
+        let digest = sighash::segwit_v0(&tx, input_index as usize, &script_code, amount, sighash_type as u32)?;
+        Ok(ecc::verify_ecdsa(&digest, der, &pubkey)?)
+    }
+
+    /// Returns a transaction's virtual size in vbytes, as fee rates are quoted in.
+    pub fn tx_vsize(&self, tx_hex: String) -> Result<u32, Vec<u8>> {
+        let tx = Transaction::parse(&decode_hex(&tx_hex)?)?;
+        Ok(tx.vsize() as u32)
+    }
+
+    /// Returns a transaction's fee rate in sat/vB, rounded down, given the amount
+    /// each of its inputs spends.
+    ///
+    /// Reverts with `LengthMismatch` unless there is one amount per input, and with
+    /// `NegativeFee` if the outputs spend more than the inputs provide.
+    pub fn tx_feerate(&self, tx_hex: String, input_amounts: Vec<u64>) -> Result<u64, Vec<u8>> {
+        let tx = Transaction::parse(&decode_hex(&tx_hex)?)?;
+        if input_amounts.len() != tx.inputs.len() {
+            return Err(VerifyError::LengthMismatch.into());
+        }
+        let inputs = input_amounts.iter().try_fold(0u64, |total, &amount| total.checked_add(amount));
+        let outputs = tx.outputs.iter().try_fold(0u64, |total, output| total.checked_add(output.value));
+        let (inputs, outputs) = inputs.zip(outputs).ok_or(VerifyError::Overflow)?;
+        let fee = inputs.checked_sub(outputs).ok_or(VerifyError::NegativeFee)?;
+        Ok(fee / tx.vsize() as u64)
+    }
+
+    /// Validates a segwit block from its header, coinbase and transaction ids.
+    ///
+    /// Returns true only if the header meets its own target, the txids hash to the
+    /// header's Merkle root with the coinbase first and no repeated tail, and the
+    /// coinbase's witness commitment matches the wtxids. All ids are hex in display order.
+    pub fn validate_full_block(
+        &self,
+        header_hex: String,
+        coinbase_tx_hex: String,
+        txids: Vec<String>,
+        wtxids: Vec<String>,
+    ) -> Result<bool, Vec<u8>> {
+        let header_bytes = decode_hex(&header_hex)?;
+        let header = Header::parse(&header_bytes)?;
+        let coinbase = Transaction::parse(&decode_hex(&coinbase_tx_hex)?)?;
+        let txids = decode_display_hashes(&txids)?;
+        let wtxids = decode_display_hashes(&wtxids)?;
+        if txids.len() != wtxids.len() {
+            return Err(VerifyError::InvalidLength.into());
+        }
+
+        if !pow::hash_meets_target(&hash::hash256(&header_bytes), pow::bits_to_target(header.bits)?) {
+            return Ok(false);
+        }
+        if txids.first() != Some(&coinbase.txid()) {
+            return Ok(false);
+        }
+        // A repeated tail would hash to the same root as the real list.
+        if merkle::merkle_root_mutated(&txids) != Some((header.merkle_root, false)) {
+            return Ok(false);
+        }
+        Ok(witness_commitment_matches(&coinbase, wtxids)?)
+    }
+
+    /// Checks a BIP325 signet block signature. `solution_hex` is the serialized
+    /// scriptSig and witness stack from the coinbase's signet commitment, and the
+    /// header's Merkle root must be the signet root, computed without it. Bare
+    /// multisig and `OP_TRUE` challenges are supported; others revert with
+    /// `UnsupportedScript`.
+    pub fn verify_signet_signature(
+        &self,
+        header_hex: String,
+        challenge_script_hex: String,
+        solution_hex: String,
+    ) -> Result<bool, Vec<u8>> {
+        let header = Header::parse(&decode_hex(&header_hex)?)?;
+        let challenge = decode_hex(&challenge_script_hex)?;
+        Ok(signet::verify_solution(&header, &challenge, &decode_hex(&solution_hex)?)?)
+    }
+
+    /// Returns the difficulty epoch `height` belongs to.
+    pub fn difficulty_epoch(&self, height: u32) -> u32 {
+        height / self.retarget_interval()
+    }
+}
+
+fn decode_hex(input: &str) -> Result<Vec<u8>, VerifyError> {
+    hex::decode(input).map_err(|_| VerifyError::InvalidHex)
+}
+
+/// Decodes hex that must be exactly `N` bytes long.
+fn decode_array<const N: usize>(input: &str) -> Result<[u8; N], VerifyError> {
+    decode_hex(input)?.try_into().map_err(|_| VerifyError::InvalidLength)
+}
+
+/// Decodes a 32-byte hash given in display order into internal byte order.
+fn decode_display_hash(input: &str) -> Result<[u8; 32], VerifyError> {
+    let mut hash: [u8; 32] = decode_array(input)?;
+    hash.reverse();
+    Ok(hash)
+}
+
+fn decode_display_hashes(inputs: &[String]) -> Result<Vec<[u8; 32]>, VerifyError> {
+    inputs.iter().map(|input| decode_display_hash(input)).collect()
+}
+
+/// Decodes a Merkle branch, rejecting one longer than `MAX_PROOF_LEN` before doing any work.
+fn decode_proof(proof: &[String]) -> Result<Vec<[u8; 32]>, VerifyError> {
+    if proof.len() > MAX_PROOF_LEN {
+        return Err(VerifyError::TooLarge);
+    }
+    decode_display_hashes(proof)
+}
+
+/// Encodes a hash held in internal byte order as display-order hex.
+fn encode_display_hash(hash: &[u8; 32]) -> String {
+    let mut display = *hash;
+    display.reverse();
+    hex::encode(display)
+}
+
+fn header_fields(header: &Header) -> HeaderFields {
+    (
+        header.version,
+        encode_display_hash(&header.prev_block),
+        encode_display_hash(&header.merkle_root),
+        header.timestamp,
+        header.bits,
+        header.nonce,
+    )
+}
+
+/// Recomputes the witness commitment from `wtxids` (internal order, coinbase first)
+/// and compares it with the one carried by `coinbase`.
+fn witness_commitment_matches(coinbase: &Transaction, mut wtxids: Vec<[u8; 32]>) -> Result<bool, VerifyError> {
+    let (Some(commitment), Some(reserved)) = (coinbase.witness_commitment()?, coinbase.witness_reserved_value()) else {
+        return Ok(false);
+    };
+    let coinbase_wtxid = wtxids.first_mut().ok_or(VerifyError::InvalidLength)?;
+    *coinbase_wtxid = [0u8; 32];
+
+    let witness_root = merkle::merkle_root(&wtxids).ok_or(VerifyError::InvalidLength)?;
+    Ok(merkle::hash_pair(&witness_root, &reserved) == commitment)
 }
 
 #[cfg(test)]
+// The slim build runs only the stateless tests, leaving relay fixtures unused.
+#[cfg_attr(not(feature = "relay"), allow(dead_code, unused_imports))]
 mod tests {
     use super::*;
+    use alloy_sol_types::SolEvent;
+    use limits::MAX_ANCESTOR_DEPTH;
+    use params::{Network, MAX_FUTURE_DRIFT, TARGET_TIMESPAN};
+    use stylus_sdk::alloy_primitives::Address;
+    use stylus_sdk::testing::*;
+
+    const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+    const GENESIS_TIMESTAMP: u32 = 1231006505;
+    const MAINNET: u8 = Network::Mainnet as u8;
+    const REGTEST: u8 = Network::Regtest as u8;
+    const SIGNET: u8 = Network::Signet as u8;
+
+    const SIGNET_GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a008f4d5fae77031e8ad22203";
+    const SIGNET_GENESIS_TIMESTAMP: u32 = 1598918400;
+
+    const REGTEST_GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4adae5494dffff7f2002000000";
+    const REGTEST_GENESIS_TIMESTAMP: u32 = 1296688602;
+    const REGTEST_BITS: u32 = 0x207fffff;
+    /// Arbitrum block time the relay helpers run at, well after every test header.
+    const ARBITRUM_TIMESTAMP: u64 = 1_750_000_000;
+
+    // Mainnet blocks 1 through 5, each building on the previous.
+    const MAINNET_HEADERS: [&str; 5] = [
+        "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299",
+        "010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61",
+        "01000000bddd99ccfda39da1b108ce1a5d70038d0a967bacb68b6b63065f626a0000000044f672226090d85db9a9f2fbfe5f0f9609b387af7be5b7fbb7a1767c831c9e995dbe6649ffff001d05e0ed6d",
+        "010000004944469562ae1c2c74d9a535e00b6f3e40ffbad4f2fda3895501b582000000007a06ea98cd40ba2e3288262b28638cec5337c1456aaf5eedc8e9e5a20f062bdf8cc16649ffff001d2bfee0a9",
+        "0100000085144a84488ea88d221c8bd6c059da090e88f8a2c99690ee55dbba4e00000000e11c48fecdd9e72510ca84f023370c9a38bf91ac5cae88019bee94d24528526344c36649ffff001d1d03e477",
+    ];
+
+    // Mainnet block 702861 (000000000000000000000c835b2adcaedc20fdf6ee440009c249452c726dafae),
+    // a 2500-transaction segwit block. Its txids and wtxids are stored packed, internal order.
+    const BLOCK_702861_HEADER: &str = "04e0ff3f5f3b2119529dc4013ff0f427a457bed706e7b5b8dec30900000000000000000022906797f7ab258c0f896c35b2d6725c0554f579af99754b241aec8c76727d4091a45561ebd00e17e3d6da41";
+    const BLOCK_702861_COINBASE: &str = "020000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff58038db90a0475a45561fabe6d6db43c2ece440513219decd96f67a31bf0191f9a5f2d6c952e5029005e3d30f562040000001e34c5f062696e616e63652f6672323134818226021704159799809b19f82f7807000000000000ffffffff02f53f8c25000000001600143156afc4249915008020f932783319f3e610b97d0000000000000000266a24aa21a9ed71bfcc287cd6271682f35f5fba3963861571e0f186899eb0a41a5ebc360a3faa0120000000000000000000000000000000000000000000000000000000000000000000000000";
+    const BLOCK_702861_TXIDS: &[u8] = include_bytes!("../testdata/mainnet_block_702861_txids.bin");
+    const BLOCK_702861_WTXIDS: &[u8] = include_bytes!("../testdata/mainnet_block_702861_wtxids.bin");
+
+    /// A relay seeded with the mainnet genesis block as its checkpoint.
+    #[cfg(feature = "relay")]
+    fn genesis_relay(vm: &TestVM) -> BtcVerifier {
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(vm);
+        verifier.initialize(GENESIS_HEADER.into(), 0, GENESIS_TIMESTAMP, MAINNET, U256::ZERO, 0, 0).unwrap();
+        verifier
+    }
+
+    /// Builds a header on top of `prev_hex` (display order) and grinds the nonce
+    /// until it meets `bits`, which is instant at regtest difficulty.
+    fn mine_header(prev_hex: &str, timestamp: u32, bits: u32) -> String {
+        let target = pow::bits_to_target(bits).unwrap();
+        let mut header = Vec::with_capacity(Header::SIZE);
+        header.extend_from_slice(&0x2000_0000u32.to_le_bytes());
+        header.extend_from_slice(&decode_display_hash(prev_hex).unwrap());
+        header.extend_from_slice(&hash::hash256(&timestamp.to_le_bytes()));
+        header.extend_from_slice(&timestamp.to_le_bytes());
+        header.extend_from_slice(&bits.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        for nonce in 0u32.. {
+            header[76..].copy_from_slice(&nonce.to_le_bytes());
+            if pow::hash_meets_target(&hash::hash256(&header), target) {
+                break;
+            }
+        }
+        hex::encode(header)
+    }
+
+    /// Display-order hash of a hex header.
+    fn block_hash(header_hex: &str) -> String {
+        encode_display_hash(&hash::hash256(&hex::decode(header_hex).unwrap()))
+    }
+
+    /// Builds the Merkle branch for `leaves[index]` (internal order) as display-order hex.
+    fn merkle_branch(leaves: &[[u8; 32]], mut index: usize) -> Vec<String> {
+        let mut level = leaves.to_vec();
+        let mut branch = Vec::new();
+        while level.len() > 1 {
+            branch.push(encode_display_hash(level.get(index ^ 1).unwrap_or(&level[index])));
+            level = level.chunks(2).map(|pair| merkle::hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0]))).collect();
+            index /= 2;
+        }
+        branch
+    }
+
+    /// Splits packed internal-order hashes into display-order hex strings.
+    fn display_hashes(packed: &[u8]) -> Vec<String> {
+        packed
+            .chunks(32)
+            .map(|chunk| encode_display_hash(&chunk.try_into().unwrap()))
+            .collect()
+    }
 
     #[test]
     fn test_double_sha256() {
@@ -48,9 +694,1713 @@ mod tests {
         // Result: 9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50
         let input = "68656c6c6f";             
         
-        let verifier = BtcVerifier {};
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
         let result = verifier.hash_btc_header(input.into()).unwrap();
 
         assert_eq!(result, "9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50");
     }
+
+    #[test]
+    fn test_difficulty_epoch_boundaries() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        assert_eq!(verifier.difficulty_epoch(2015), 0);
+        assert_eq!(verifier.difficulty_epoch(2016), 1);
+        assert_eq!(verifier.difficulty_epoch(2017), 1);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_retarget_anchor_requires_checkpoint() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        assert_eq!(verifier.retarget_anchor(), Err(VerifyError::NotInitialized.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_retarget_anchor_around_boundary() {
+        // The genesis header stands in for a checkpoint at each height; only
+        // the height arithmetic matters here.
+        let anchor_ts = 1_233_000_000;
+
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(GENESIS_HEADER.into(), 2015, anchor_ts, MAINNET, U256::ZERO, 0, 0).unwrap();
+        assert_eq!(verifier.retarget_anchor().unwrap(), (0, anchor_ts));
+
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(GENESIS_HEADER.into(), 2016, GENESIS_TIMESTAMP, MAINNET, U256::ZERO, 0, 0).unwrap();
+        assert_eq!(verifier.retarget_anchor().unwrap(), (2016, GENESIS_TIMESTAMP));
+
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(GENESIS_HEADER.into(), 2017, anchor_ts, MAINNET, U256::ZERO, 0, 0).unwrap();
+        assert_eq!(verifier.retarget_anchor().unwrap(), (2016, anchor_ts));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_initialize_rejects_mismatched_boundary_anchor() {
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+
+        let result = verifier.initialize(GENESIS_HEADER.into(), 2016, GENESIS_TIMESTAMP + 1, MAINNET, U256::ZERO, 0, 0);
+        assert_eq!(result, Err(VerifyError::InvalidAnchor.into()));
+    }
+
+    #[test]
+    fn test_verify_pow_genesis() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        assert!(verifier.verify_pow(GENESIS_HEADER.into()).unwrap());
+
+        // Bumping the nonce breaks the proof of work.
+        let tampered = GENESIS_HEADER.replace("1dac2b7c", "1dac2b7d");
+        assert!(!verifier.verify_pow(tampered).unwrap());
+    }
+
+    #[test]
+    fn test_merkle_root_from_txids_single() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        // The genesis block has only a coinbase, so its txid is the root.
+        let coinbase = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        assert_eq!(verifier.merkle_root_from_txids(vec![coinbase.into()]).unwrap(), coinbase);
+    }
+
+    #[test]
+    fn test_validate_full_block_segwit() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        let txids = display_hashes(BLOCK_702861_TXIDS);
+        let wtxids = display_hashes(BLOCK_702861_WTXIDS);
+        assert_eq!(
+            verifier.merkle_root_from_txids(txids.clone()).unwrap(),
+            "407d72768cec1a244b7599af79f554055c72d6b2356c890f8c25abf797679022"
+        );
+        assert!(verifier.verify_witness_commitment(BLOCK_702861_COINBASE.into(), wtxids.clone()).unwrap());
+        assert!(verifier
+            .validate_full_block(BLOCK_702861_HEADER.into(), BLOCK_702861_COINBASE.into(), txids.clone(), wtxids.clone())
+            .unwrap());
+
+        // Swapping two wtxids breaks only the witness commitment.
+        let mut swapped = wtxids.clone();
+        swapped.swap(1, 2);
+        assert!(!verifier
+            .validate_full_block(BLOCK_702861_HEADER.into(), BLOCK_702861_COINBASE.into(), txids.clone(), swapped)
+            .unwrap());
+
+        // CVE-2012-2459: repeating the last two txids would keep the root of a block
+        // with 4k + 2 transactions. 2500 leave an odd 625 nodes two levels up instead,
+        // so here it takes the last four. Only the duplicate-sibling check catches it.
+        let mut doubled = txids.clone();
+        doubled.extend_from_within(2498..);
+        assert_ne!(verifier.merkle_root_from_txids(doubled.clone()), verifier.merkle_root_from_txids(txids.clone()));
+        let (mut long_txids, mut long_wtxids) = (txids.clone(), wtxids.clone());
+        long_txids.extend_from_within(2496..);
+        long_wtxids.extend_from_within(2496..);
+        assert_eq!(verifier.merkle_root_from_txids(long_txids.clone()), verifier.merkle_root_from_txids(txids.clone()));
+        assert!(verifier.verify_witness_commitment(BLOCK_702861_COINBASE.into(), long_wtxids.clone()).unwrap());
+        assert!(!verifier
+            .validate_full_block(BLOCK_702861_HEADER.into(), BLOCK_702861_COINBASE.into(), long_txids, long_wtxids)
+            .unwrap());
+
+        // Dropping a transaction breaks the Merkle root.
+        let (mut short_txids, mut short_wtxids) = (txids, wtxids);
+        short_txids.pop();
+        short_wtxids.pop();
+        assert!(!verifier
+            .validate_full_block(BLOCK_702861_HEADER.into(), BLOCK_702861_COINBASE.into(), short_txids, short_wtxids)
+            .unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_submit_header_extends_tip() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+
+        assert_eq!(verifier.submit_header(MAINNET_HEADERS[0].into()).unwrap(), 1);
+        assert_eq!(verifier.latest_height().unwrap(), 1);
+        assert_eq!(verifier.get_header(1).unwrap(), MAINNET_HEADERS[0]);
+
+        // Block 3 doesn't build on block 1.
+        let result = verifier.submit_header(MAINNET_HEADERS[2].into());
+        assert_eq!(result, Err(VerifyError::PrevHashMismatch.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_sync_resumes_from_cursor() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        assert_eq!(verifier.sync_cursor(), 0);
+
+        // The first batch lands, then the job dies before sending the rest.
+        let first_batch = MAINNET_HEADERS[..2].iter().map(|h| h.to_string()).collect();
+        verifier.submit_headers(first_batch).unwrap();
+        assert_eq!(verifier.sync_cursor(), 2);
+
+        // On restart the job skips everything up to the cursor. Headers start at height 1.
+        let resume_from = verifier.sync_cursor() as usize;
+        let remaining = MAINNET_HEADERS[resume_from..].iter().map(|h| h.to_string()).collect();
+        assert_eq!(verifier.submit_headers(remaining).unwrap(), 5);
+        assert_eq!(verifier.sync_cursor(), 5);
+        assert_eq!(verifier.get_header(5).unwrap(), MAINNET_HEADERS[4]);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_sync_cursor_follows_single_submissions() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        verifier.submit_headers(MAINNET_HEADERS[..2].iter().map(|h| h.to_string()).collect()).unwrap();
+        // Someone else relays the next block on its own.
+        verifier.submit_header(MAINNET_HEADERS[2].into()).unwrap();
+        assert_eq!(verifier.sync_cursor(), 3);
+
+        // Resuming from the cursor neither repeats block 3 nor skips past it.
+        let resume_from = verifier.sync_cursor() as usize;
+        let remaining = MAINNET_HEADERS[resume_from..].iter().map(|h| h.to_string()).collect();
+        assert_eq!(verifier.submit_headers(remaining).unwrap(), 5);
+        assert_eq!(verifier.sync_cursor(), 5);
+    }
+
+    #[test]
+    fn test_next_bits_first_mainnet_retarget() {
+        // From Bitcoin Core's pow_tests: blocks 30240 and 32255 bracket the first
+        // epoch that actually retargeted.
+        let next = pow::next_bits(0x1d00ffff, 1261130161, 1262152739, TARGET_TIMESPAN, Network::Mainnet.pow_limit());
+        assert_eq!(next, Ok(0x1d00d86a));
+
+        // An epoch slower than two weeks can't drop below the pow limit.
+        let next = pow::next_bits(0x1d00ffff, GENESIS_TIMESTAMP, 1233061996, TARGET_TIMESPAN, Network::Mainnet.pow_limit());
+        assert_eq!(next, Ok(0x1d00ffff));
+    }
+
+    #[test]
+    fn test_compact_bits_match_core() {
+        // Cases from Bitcoin Core's arith_uint256 SetCompact/GetCompact tests.
+        // A zero mantissa is zero whatever the exponent.
+        assert_eq!(pow::bits_to_target(0x03000000), Ok(U256::ZERO));
+        assert_eq!(pow::target_to_bits(U256::ZERO), 0);
+
+        // Exponents below 3 shift the mantissa right, here entirely away...
+        assert_eq!(pow::bits_to_target(0x01003456), Ok(U256::ZERO));
+        assert_eq!(pow::bits_to_target(0x00123456), Ok(U256::ZERO));
+        // ...or down to a single byte, which re-encodes in the shortest form.
+        assert_eq!(pow::bits_to_target(0x01123456), Ok(U256::from(0x12)));
+        assert_eq!(pow::target_to_bits(U256::from(0x12)), 0x01120000);
+
+        // A set high mantissa bit is the sign: negative targets are invalid.
+        assert_eq!(pow::bits_to_target(0x04923456), Err(VerifyError::NegativeTarget));
+
+        // A target whose top byte has the high bit set needs a leading zero byte.
+        assert_eq!(pow::bits_to_target(0x05009234), Ok(U256::from(0x92340000u64)));
+        assert_eq!(pow::target_to_bits(U256::from(0x92340000u64)), 0x05009234);
+
+        // Mantissas shifted past 256 bits overflow.
+        assert_eq!(pow::bits_to_target(0xff123456), Err(VerifyError::TargetOverflow));
+        assert_eq!(pow::bits_to_target(0x21010000), Err(VerifyError::TargetOverflow));
+        assert!(pow::bits_to_target(0x20010000).is_ok());
+    }
+
+    #[test]
+    fn test_pow_limits_match_core() {
+        // Core's signet powLimit is exactly its genesis target.
+        assert_eq!(Network::Signet.pow_limit(), pow::bits_to_target(0x1e0377ae).unwrap());
+        // Regtest's has every bit below the top one set, so it is looser than the
+        // target of its genesis bits, 0x207fffff, which nBits can't express exactly.
+        let regtest_limit = "7fffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff";
+        assert_eq!(Network::Regtest.pow_limit(), U256::from_str_radix(regtest_limit, 16).unwrap());
+        assert!(Network::Regtest.pow_limit() > pow::bits_to_target(REGTEST_BITS).unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_tip_header_parsed_after_checkpoint() {
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        assert_eq!(verifier.tip_header_parsed(), Err(VerifyError::NotInitialized.into()));
+
+        verifier.initialize(MAINNET_HEADERS[0].into(), 1, GENESIS_TIMESTAMP, MAINNET, U256::ZERO, 0, 0).unwrap();
+        let (version, prev_block, merkle_root, timestamp, bits, nonce) = verifier.tip_header_parsed().unwrap();
+        assert_eq!(version, 1);
+        assert_eq!(prev_block, "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+        assert_eq!(merkle_root, "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098");
+        assert_eq!(timestamp, 1231469665);
+        assert_eq!(bits, 0x1d00ffff);
+        assert_eq!(nonce, 2573394689);
+
+        assert_eq!(verifier.tip_header_parsed(), verifier.parse_header(MAINNET_HEADERS[0].into()));
+    }
+
+    #[test]
+    fn test_hash_btc_header_bytes_matches_hex_path() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        let from_bytes = verifier.hash_btc_header_bytes(hex::decode(GENESIS_HEADER).unwrap()).unwrap();
+        let from_hex = verifier.hash_btc_header(GENESIS_HEADER.into()).unwrap();
+        assert_eq!(hex::encode(from_bytes), from_hex);
+
+        assert_eq!(verifier.hash_btc_header_bytes(vec![0u8; 79]), Err(VerifyError::InvalidLength.into()));
+    }
+
+    /// Compares the string and bytes hashing paths on an 80-byte header. Only the
+    /// native Rust work is timed; Stylus gas also charges for ABI decoding, which
+    /// favours the bytes path further.
+    #[cfg(feature = "bench")]
+    #[test]
+    fn bench_hash_paths() {
+        use std::time::Instant;
+
+        const ITERATIONS: u32 = 100_000;
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let header_bytes = hex::decode(GENESIS_HEADER).unwrap();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(verifier.hash_btc_header(std::hint::black_box(GENESIS_HEADER.into())).unwrap());
+        }
+        let string_path = start.elapsed() / ITERATIONS;
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            std::hint::black_box(verifier.hash_btc_header_bytes(std::hint::black_box(header_bytes.clone())).unwrap());
+        }
+        let bytes_path = start.elapsed() / ITERATIONS;
+
+        println!("hash_btc_header       (hex string): {string_path:?}/call");
+        println!("hash_btc_header_bytes (raw bytes):  {bytes_path:?}/call");
+        println!("bytes path speedup: {:.2}x", string_path.as_secs_f64() / bytes_path.as_secs_f64());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_regtest_skips_pow_but_enforces_linkage() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier
+            .initialize(REGTEST_GENESIS_HEADER.into(), 0, REGTEST_GENESIS_TIMESTAMP, REGTEST, U256::ZERO, 0, 0)
+            .unwrap();
+        let regtest_limit = Network::Regtest.pow_limit();
+        assert_eq!(verifier.pow_limit().unwrap(), regtest_limit);
+
+        let mut prev = block_hash(REGTEST_GENESIS_HEADER);
+        for height in 1..=3 {
+            let header = mine_header(&prev, REGTEST_GENESIS_TIMESTAMP + height, REGTEST_BITS);
+            assert_eq!(verifier.submit_header(header.clone()).unwrap(), height);
+            prev = block_hash(&header);
+        }
+
+        // Every block counts as exactly pow_limit work, on top of the checkpoint's.
+        let block_work = pow::work(regtest_limit).unwrap();
+        assert_eq!(block_work, U256::from(2));
+        assert_eq!(verifier.chainwork.get(verifier.tip_hash.get()), block_work * U256::from(4));
+
+        // A header whose hash misses its target is still accepted on regtest...
+        let mut unmined = hex::decode(mine_header(&prev, 0, REGTEST_BITS)).unwrap();
+        while verifier.verify_pow(hex::encode(&unmined)).unwrap() {
+            unmined[76] = unmined[76].wrapping_add(1);
+        }
+        assert!(!verifier.verify_pow(hex::encode(&unmined)).unwrap());
+        assert_eq!(verifier.submit_header(hex::encode(&unmined)).unwrap(), 4);
+
+        // ...but one that doesn't build on the tip is not.
+        let orphan = mine_header(&prev, 0, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(orphan), Err(VerifyError::PrevHashMismatch.into()));
+    }
+
+    #[test]
+    fn test_verify_schnorr_batch() {
+        // BIP340 test vectors 0 to 3 as (message, signature, public key).
+        let vectors = [
+            (
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0",
+                "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+            ),
+            (
+                "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89",
+                "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de33418906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a",
+                "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659",
+            ),
+            (
+                "7e2d58d8b3bcdf1abadec7829054f90dda9805aab56c77333024b9d0a508b75c",
+                "5831aaeed7b44bb74e5eab94ba9d4294c49bcf2a60728d8b4c200f50dd313c1bab745879a5ad954a72c45a91c3a51d3c7adea98d82f8481e0e1e03674a6f3fb7",
+                "dd308afec5777e13121fa72b9cc1b7cc0139715309b086c960e18fd969774eb8",
+            ),
+            (
+                "ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff",
+                "7eb0509757e246f19449885651611cb965ecc1a187dd51b64fda1edc9637d5ec97582b9cb13db3933705b32ba982af5af25fd78881ebb32771fc5922efc66ea3",
+                "25d1dff95105f5253c4022f628a996ad3a0d95fbf21d468a1b33f8c160d8f517",
+            ),
+        ];
+        let msgs: Vec<String> = vectors.iter().map(|v| v.0.into()).collect();
+        let sigs: Vec<String> = vectors.iter().map(|v| v.1.into()).collect();
+        let pubkeys: Vec<String> = vectors.iter().map(|v| v.2.into()).collect();
+
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        assert!(verifier.verify_schnorr_batch(msgs.clone(), sigs.clone(), pubkeys.clone()).unwrap());
+
+        // Corrupting a single signature fails the whole batch.
+        let mut tampered = sigs.clone();
+        tampered[2] = tampered[2].replace("3fb7", "3fb8");
+        assert!(!verifier.verify_schnorr(msgs[2].clone(), tampered[2].clone(), pubkeys[2].clone()).unwrap());
+        assert!(!verifier.verify_schnorr_batch(msgs.clone(), tampered, pubkeys.clone()).unwrap());
+
+        let result = verifier.verify_schnorr_batch(msgs, sigs[..3].to_vec(), pubkeys);
+        assert_eq!(result, Err(VerifyError::LengthMismatch.into()));
+    }
+
+    #[test]
+    fn test_verify_schnorr_key_off_curve() {
+        // BIP340 test vector 5: no point on the curve has this x-coordinate.
+        let msg = "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89";
+        let sig = "6cff5c3ba86c69ea4b7376f31a9bcb4f74c1976089b2d9963da2e5543e17776969e89b4c5564d00349106b8497785dd7d1d713a8ae82b32fa79d5f7fc407d39b";
+        let off_curve = "eefdea4cdb677750a420fee807eacf21eb9898ae79b9768766e4faa04a2d4a34";
+
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        assert_eq!(verifier.verify_schnorr(msg.into(), sig.into(), off_curve.into()), Ok(false));
+
+        // One such key fails its batch instead of reverting it.
+        let valid = (
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "e907831f80848d1069a5371b402410364bdf1c5f8307b0084c55f1ce2dca821525f66a4a85ea8b71e482a74f382d2ce5ebeee8fdb2172f477df4900d310536c0",
+            "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        );
+        let result = verifier.verify_schnorr_batch(
+            vec![valid.0.into(), msg.into()],
+            vec![valid.1.into(), sig.into()],
+            vec![valid.2.into(), off_curve.into()],
+        );
+        assert_eq!(result, Ok(false));
+    }
+
+    #[test]
+    fn test_merkle_depth() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        assert_eq!(verifier.merkle_depth(1), 0);
+        assert_eq!(verifier.merkle_depth(2), 1);
+        assert_eq!(verifier.merkle_depth(3), 2);
+        assert_eq!(verifier.merkle_depth(7), 3);
+        // Block 702861 has 2500 transactions.
+        assert_eq!(verifier.merkle_depth(2500), 12);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_initialize_only_once() {
+        let vm = TestVM::default();
+        let deployer = Address::from([0x11; 20]);
+        let attacker = Address::from([0x22; 20]);
+
+        vm.set_sender(deployer);
+        let mut verifier = genesis_relay(&vm);
+        assert_eq!(verifier.owner(), deployer);
+
+        vm.set_sender(attacker);
+        let result = verifier.initialize(MAINNET_HEADERS[0].into(), 1, GENESIS_TIMESTAMP, MAINNET, U256::ZERO, 0, 0);
+        assert_eq!(result, Err(VerifyError::AlreadyInitialized.into()));
+        assert_eq!(verifier.owner(), deployer);
+        assert_eq!(verifier.latest_height().unwrap(), 0);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let vm = TestVM::default();
+        let owner = Address::from([0x11; 20]);
+        let successor = Address::from([0x33; 20]);
+
+        vm.set_sender(owner);
+        let mut verifier = genesis_relay(&vm);
+        verifier.transfer_ownership(successor).unwrap();
+        assert_eq!(verifier.pending_owner(), successor);
+        assert_eq!(verifier.owner(), owner);
+
+        vm.set_sender(successor);
+        verifier.accept_ownership().unwrap();
+        assert_eq!(verifier.owner(), successor);
+        assert_eq!(verifier.pending_owner(), Address::ZERO);
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, _) = &logs[0];
+        assert_eq!(topics[0], OwnershipTransferred::SIGNATURE_HASH);
+        assert_eq!(topics[1], owner.into_word());
+        assert_eq!(topics[2], successor.into_word());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_only_pending_owner_can_accept() {
+        let vm = TestVM::default();
+        let owner = Address::from([0x11; 20]);
+        let stranger = Address::from([0x22; 20]);
+
+        vm.set_sender(owner);
+        let mut verifier = genesis_relay(&vm);
+
+        // Nothing pending yet: not even the owner can accept.
+        assert_eq!(verifier.accept_ownership(), Err(VerifyError::NotPendingOwner.into()));
+
+        verifier.transfer_ownership(Address::from([0x33; 20])).unwrap();
+        vm.set_sender(stranger);
+        assert_eq!(verifier.accept_ownership(), Err(VerifyError::NotPendingOwner.into()));
+        assert_eq!(verifier.transfer_ownership(stranger), Err(VerifyError::NotOwner.into()));
+        assert_eq!(verifier.owner(), owner);
+    }
+
+    /// A regtest relay seeded with the regtest genesis block.
+    #[cfg(feature = "relay")]
+    fn regtest_relay(vm: &TestVM) -> BtcVerifier {
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(vm);
+        verifier
+            .initialize(REGTEST_GENESIS_HEADER.into(), 0, REGTEST_GENESIS_TIMESTAMP, REGTEST, U256::ZERO, 0, 0)
+            .unwrap();
+        verifier
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_known_and_canonical_blocks() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        let a2 = mine_header(&block_hash(&a1), REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        verifier.submit_header(a1.clone()).unwrap();
+        verifier.submit_header(a2.clone()).unwrap();
+
+        // A competing block 1 has no more work than the tip, so it stays stale.
+        let b1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 100, REGTEST_BITS);
+        assert_eq!(verifier.submit_header_fork(b1.clone()).unwrap(), 1);
+        assert_eq!(verifier.latest_height().unwrap(), 2);
+
+        assert!(verifier.is_known_block(block_hash(&a1)).unwrap());
+        assert!(verifier.is_canonical_block(block_hash(&a1)).unwrap());
+        assert!(verifier.is_canonical_block(genesis.clone()).unwrap());
+        assert!(verifier.is_known_block(block_hash(&b1)).unwrap());
+        assert!(!verifier.is_canonical_block(block_hash(&b1)).unwrap());
+
+        let unknown = "11".repeat(32);
+        assert!(!verifier.is_known_block(unknown.clone()).unwrap());
+        assert!(!verifier.is_canonical_block(unknown.clone()).unwrap());
+        let orphan = mine_header(&unknown, REGTEST_GENESIS_TIMESTAMP, REGTEST_BITS);
+        assert_eq!(verifier.submit_header_fork(orphan), Err(VerifyError::UnknownBlock.into()));
+
+        // Extending the fork past the tip's work switches the best chain to it.
+        let b2 = mine_header(&block_hash(&b1), REGTEST_GENESIS_TIMESTAMP + 101, REGTEST_BITS);
+        let b3 = mine_header(&block_hash(&b2), REGTEST_GENESIS_TIMESTAMP + 102, REGTEST_BITS);
+        verifier.submit_header_fork(b2).unwrap();
+        assert_eq!(verifier.latest_height().unwrap(), 2);
+        assert_eq!(verifier.submit_header_fork(b3.clone()).unwrap(), 3);
+        assert_eq!(verifier.latest_height().unwrap(), 3);
+        assert_eq!(verifier.tip_hash.get().0, decode_display_hash(&block_hash(&b3)).unwrap());
+        assert!(verifier.is_canonical_block(block_hash(&b1)).unwrap());
+        assert!(!verifier.is_canonical_block(block_hash(&a1)).unwrap());
+        assert!(!verifier.is_canonical_block(block_hash(&a2)).unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_verify_prev_is_canonical() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        let b1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 100, REGTEST_BITS);
+        verifier.submit_header(a1.clone()).unwrap();
+        verifier.submit_header_fork(b1.clone()).unwrap();
+
+        // Neither child is submitted; only its parent is looked up.
+        let on_tip = mine_header(&block_hash(&a1), REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        assert!(verifier.verify_prev_is_canonical(on_tip).unwrap());
+        let on_genesis = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        assert!(verifier.verify_prev_is_canonical(on_genesis).unwrap());
+        let on_stale = mine_header(&block_hash(&b1), REGTEST_GENESIS_TIMESTAMP + 101, REGTEST_BITS);
+        assert!(!verifier.verify_prev_is_canonical(on_stale).unwrap());
+        let on_unknown = mine_header(&"11".repeat(32), REGTEST_GENESIS_TIMESTAMP, REGTEST_BITS);
+        assert!(!verifier.verify_prev_is_canonical(on_unknown).unwrap());
+        assert_eq!(verifier.latest_height().unwrap(), 1);
+        assert_eq!(verifier.verify_prev_is_canonical("00".repeat(79)), Err(VerifyError::InvalidLength.into()));
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        // Mainnet block 00000000b0c5a240b2a61d2e75692224efd4cbecdf6eaf4cc2cf477ca7c270e7 has two transactions.
+        let root = "4c917a410f4e899195f816081844e56aceda71c4cc4fe634aebe9437e57344bf";
+        let coinbase = "77dfc2fe598419b00641c296181a96cf16943697f573480b023b77cce82ada21";
+        let spend = "a3b0e9e7cddbbe78270fa4182a7675ff00b92872d8df7d14265a2b1e379a9d33";
+
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        assert!(verifier.verify_merkle_proof(spend.into(), root.into(), vec![coinbase.into()], 1).unwrap());
+        assert!(verifier.verify_merkle_proof(coinbase.into(), root.into(), vec![spend.into()], 0).unwrap());
+
+        // The wrong position, or one past the end of the branch, doesn't verify.
+        assert!(!verifier.verify_merkle_proof(spend.into(), root.into(), vec![coinbase.into()], 0).unwrap());
+        assert!(!verifier.verify_merkle_proof(spend.into(), root.into(), vec![coinbase.into()], 3).unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_verify_tx_at_height_confirmations() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        verifier.submit_headers(MAINNET_HEADERS.iter().map(|h| h.to_string()).collect()).unwrap();
+
+        // Block 1 holds only its coinbase, so the txid is the root and the proof is empty.
+        let coinbase = "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098";
+        // With the tip at 5, block 1 has exactly five confirmations.
+        assert!(verifier.verify_tx_at_height(coinbase.into(), 1, vec![], 0, 5).unwrap());
+        assert_eq!(
+            verifier.verify_tx_at_height(coinbase.into(), 1, vec![], 0, 6),
+            Err(VerifyError::InsufficientConfirmations.into())
+        );
+        assert!(!verifier.verify_tx_at_height(coinbase.into(), 2, vec![], 0, 1).unwrap());
+        assert_eq!(
+            verifier.verify_tx_at_height(coinbase.into(), 6, vec![], 0, 0),
+            Err(VerifyError::UnknownBlock.into())
+        );
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_audit_chain() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        verifier.submit_headers(MAINNET_HEADERS.iter().map(|h| h.to_string()).collect()).unwrap();
+
+        assert!(verifier.audit_chain(0, 6).unwrap());
+        assert!(verifier.audit_chain(3, 3).unwrap());
+        assert_eq!(verifier.audit_chain(4, 3), Err(VerifyError::UnknownBlock.into()));
+        assert_eq!(verifier.audit_chain(0, MAX_ANCESTOR_DEPTH + 1), Err(VerifyError::TooLarge.into()));
+
+        // Overwrite block 3's stored bytes with block 4's.
+        let hash = verifier.canonical_hash_at.get(3);
+        verifier.headers.setter(hash).set_bytes(hex::decode(MAINNET_HEADERS[3]).unwrap());
+        assert!(!verifier.audit_chain(0, 6).unwrap());
+        assert!(verifier.audit_chain(4, 2).unwrap());
+    }
+
+    #[test]
+    fn test_header_bytes_round_trip() {
+        let bytes: [u8; Header::SIZE] = hex::decode(GENESIS_HEADER).unwrap().try_into().unwrap();
+        let header = Header::from_bytes(&bytes);
+        assert_eq!(header.to_bytes(), bytes);
+        assert_eq!(header.timestamp, GENESIS_TIMESTAMP);
+
+        assert_eq!(Header::try_from(&bytes[..]), Ok(header.clone()));
+        assert_eq!(<[u8; Header::SIZE]>::from(&header), bytes);
+        assert_eq!(Header::try_from(&bytes[..79]), Err(VerifyError::InvalidLength));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_loop_limits_enforced_at_boundary() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let zero = "00".repeat(32);
+
+        // Proofs: the cap itself is checked normally, one more sibling is refused.
+        let proof = vec![zero.clone(); MAX_PROOF_LEN];
+        assert!(!verifier.verify_merkle_proof(zero.clone(), zero.clone(), proof.clone(), 0).unwrap());
+        let mut long_proof = proof;
+        long_proof.push(zero.clone());
+        let result = verifier.verify_merkle_proof(zero.clone(), zero.clone(), long_proof.clone(), 0);
+        assert_eq!(result, Err(VerifyError::TooLarge.into()));
+        let result = verifier.verify_tx_at_height(zero.clone(), 0, long_proof, 0, 0);
+        assert_eq!(result, Err(VerifyError::TooLarge.into()));
+
+        // Batches: a full batch of headers is accepted, one more is refused up front.
+        let mut prev = block_hash(REGTEST_GENESIS_HEADER);
+        let mut batch = Vec::new();
+        for height in 1..=MAX_BATCH as u32 {
+            batch.push(mine_header(&prev, REGTEST_GENESIS_TIMESTAMP + height, REGTEST_BITS));
+            prev = block_hash(batch.last().unwrap());
+        }
+        assert_eq!(verifier.submit_headers(batch.clone()).unwrap(), MAX_BATCH as u32);
+        batch.push(batch[0].clone());
+        assert_eq!(verifier.submit_headers(batch), Err(VerifyError::TooLarge.into()));
+        let triples = vec![zero.clone(); MAX_BATCH + 1];
+        let result = verifier.verify_schnorr_batch(triples.clone(), triples.clone(), triples);
+        assert_eq!(result, Err(VerifyError::TooLarge.into()));
+
+        // Ancestor walks: a block exactly MAX_ANCESTOR_DEPTH below the tip is reachable.
+        for height in MAX_BATCH as u32 + 1..=MAX_ANCESTOR_DEPTH + 1 {
+            let header = mine_header(&prev, REGTEST_GENESIS_TIMESTAMP + height, REGTEST_BITS);
+            verifier.submit_header(header.clone()).unwrap();
+            prev = block_hash(&header);
+        }
+        // Canonicity is a lookup, so even the checkpoint far below the tip resolves.
+        let block_1 = encode_display_hash(&verifier.canonical_hash_at.get(1).0);
+        assert!(verifier.is_canonical_block(block_1).unwrap());
+        assert!(verifier.is_canonical_block(block_hash(REGTEST_GENESIS_HEADER)).unwrap());
+        assert!(verifier.audit_chain(1, MAX_ANCESTOR_DEPTH).unwrap());
+        assert_eq!(verifier.audit_chain(0, MAX_ANCESTOR_DEPTH + 1), Err(VerifyError::TooLarge.into()));
+    }
+
+    #[test]
+    fn test_last_witness_commitment_counts() {
+        let coinbase = Transaction::parse(&hex::decode(BLOCK_702861_COINBASE).unwrap()).unwrap();
+        let wtxids = decode_display_hashes(&display_hashes(BLOCK_702861_WTXIDS)).unwrap();
+        assert_eq!(witness_commitment_matches(&coinbase, wtxids.clone()), Ok(true));
+        let real = coinbase.outputs.iter().position(|output| output.script_pubkey.starts_with(&[0x6a, 0x24])).unwrap();
+        let mut decoy = coinbase.outputs[real].clone();
+        decoy.script_pubkey[6..38].copy_from_slice(&[0x11; 32]);
+
+        // A commitment-like output before the real one is ignored...
+        let mut earlier = coinbase.clone();
+        earlier.outputs.insert(real, decoy.clone());
+        assert_eq!(witness_commitment_matches(&earlier, wtxids.clone()), Ok(true));
+        // ...but one after it takes precedence.
+        let mut later = coinbase.clone();
+        later.outputs.push(decoy.clone());
+        assert_eq!(witness_commitment_matches(&later, wtxids.clone()), Ok(false));
+
+        let mut truncated = coinbase;
+        decoy.script_pubkey.truncate(37);
+        truncated.outputs.push(decoy);
+        assert_eq!(witness_commitment_matches(&truncated, wtxids), Err(VerifyError::MalformedTx));
+    }
+
+    #[test]
+    fn test_coinbase_witness_reserved() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        // Block 702861's coinbase commits with the customary all-zero reserved value.
+        let reserved = verifier.coinbase_witness_reserved(BLOCK_702861_COINBASE.into()).unwrap();
+        assert_eq!(reserved, "00".repeat(32));
+
+        // Reserved values of any other length are rejected.
+        // Swap the witness (one 32-byte item, then the lock time) for a 31-byte item.
+        let witness_start = BLOCK_702861_COINBASE.len() - 2 * (2 + 32 + 4);
+        let short = format!("{}011f{}00000000", &BLOCK_702861_COINBASE[..witness_start], "00".repeat(31));
+        assert!(Transaction::parse(&hex::decode(&short).unwrap()).is_ok());
+        assert_eq!(verifier.coinbase_witness_reserved(short), Err(VerifyError::MissingWitnessReserved.into()));
+
+        // A pre-segwit coinbase (block 00000000b0c5a240...) has no witness at all.
+        let legacy = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0804ffff001d026e04ffffffff0100f2052a0100000043410446ef0102d1ec5240f0d061a4246c1bdef63fc3dbab7733052fbbf0ecd8f41fc26bf049ebb4f9527f374280259e7cfa99c48b0e3f39c51347a19a5819651503a5ac00000000";
+        assert_eq!(verifier.coinbase_witness_reserved(legacy.into()), Err(VerifyError::MissingWitnessReserved.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_work_arithmetic_near_limits() {
+        // The easiest representable targets still have well-defined work...
+        assert_eq!(pow::work(U256::MAX - U256::from(1)), Ok(U256::from(1)));
+        assert_eq!(pow::work(U256::MAX >> 1), Ok(U256::from(2)));
+        assert_eq!(pow::work(U256::from(1)), Ok(U256::from(1) << 255));
+        // ...but the very top one would wrap target + 1, and a zero target's work is 2^256.
+        assert_eq!(pow::work(U256::MAX), Err(VerifyError::Overflow));
+        assert_eq!(pow::work(U256::ZERO), Err(VerifyError::Overflow));
+
+        // Quadrupling a near-maximal target doesn't fit in 256 bits.
+        let slow_epoch = 4 * TARGET_TIMESPAN;
+        assert_eq!(pow::next_bits(0x207fffff, 0, slow_epoch, TARGET_TIMESPAN, U256::MAX), Err(VerifyError::Overflow));
+        assert_eq!(pow::next_bits(0x1d00ffff, 0, slow_epoch, TARGET_TIMESPAN, U256::MAX), Ok(0x1d03fffc));
+
+        // Chainwork that would pass 2^256 reverts instead of wrapping.
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let tip = verifier.tip_hash.get();
+        verifier.chainwork.insert(tip, U256::MAX - U256::from(1));
+        let header = mine_header(&block_hash(REGTEST_GENESIS_HEADER), REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(header), Err(VerifyError::Overflow.into()));
+        assert_eq!(verifier.latest_height().unwrap(), 0);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_predict_next_bits_first_mainnet_retarget() {
+        // Block 32255 closed the first epoch that retargeted, which started at 1261130161.
+        // The genesis header stands in for it; only its 0x1d00ffff bits matter.
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(GENESIS_HEADER.into(), 32255, 1261130161, MAINNET, U256::ZERO, 0, 0).unwrap();
+        assert_eq!(verifier.predict_next_bits(1262152739), Ok(0x1d00d86a));
+
+        let vm = TestVM::default();
+        let verifier = genesis_relay(&vm);
+        assert_eq!(verifier.predict_next_bits(GENESIS_TIMESTAMP), Err(VerifyError::NotAtRetarget.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_submit_and_prove() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+
+        // Block 1's only transaction is its coinbase, proven with an empty branch.
+        let coinbase = "0e3e2357e806b6cdb1f70b54c3a3a17b6714ee1f0e68bebb44a74b1efd512098";
+        assert!(verifier.submit_and_prove(MAINNET_HEADERS[0].into(), coinbase.into(), vec![], 0).unwrap());
+        assert_eq!(verifier.latest_height().unwrap(), 1);
+
+        // A txid that isn't in block 2 fails the proof, and the header isn't kept.
+        let result = verifier.submit_and_prove(MAINNET_HEADERS[1].into(), coinbase.into(), vec![], 0);
+        assert_eq!(result, Err(VerifyError::InvalidProof.into()));
+        assert_eq!(verifier.latest_height().unwrap(), 1);
+
+        // A valid proof doesn't excuse a header that doesn't link to the tip.
+        let block_5_coinbase = verifier.parse_header(MAINNET_HEADERS[4].into()).unwrap().2;
+        let result = verifier.submit_and_prove(MAINNET_HEADERS[4].into(), block_5_coinbase, vec![], 0);
+        assert_eq!(result, Err(VerifyError::PrevHashMismatch.into()));
+        assert_eq!(verifier.latest_height().unwrap(), 1);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_header_exists() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        assert!(!verifier.header_exists(0));
+
+        verifier.initialize(GENESIS_HEADER.into(), 0, GENESIS_TIMESTAMP, MAINNET, U256::ZERO, 0, 0).unwrap();
+        verifier.submit_headers(MAINNET_HEADERS[..2].iter().map(|h| h.to_string()).collect()).unwrap();
+        assert!((0..=2).all(|height| verifier.header_exists(height)));
+        assert!(!verifier.header_exists(3));
+        assert!(!verifier.header_exists(u32::MAX));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_signet_pow_limit_override() {
+        let signet_genesis = block_hash(SIGNET_GENESIS_HEADER);
+        assert_eq!(signet_genesis, "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6");
+        // A custom signet's genesis and blocks, mined at regtest difficulty.
+        let custom_genesis = mine_header(&"00".repeat(32), SIGNET_GENESIS_TIMESTAMP, REGTEST_BITS);
+        let easy_header = mine_header(&block_hash(&custom_genesis), SIGNET_GENESIS_TIMESTAMP + 600, REGTEST_BITS);
+
+        // By default a signet relay holds headers to signet's own limit.
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(custom_genesis.clone(), 0, SIGNET_GENESIS_TIMESTAMP, SIGNET, U256::ZERO, 0, 0).unwrap();
+        assert_eq!(verifier.pow_limit().unwrap(), Network::Signet.pow_limit());
+        assert_eq!(verifier.submit_header(easy_header.clone()), Err(VerifyError::InsufficientPow.into()));
+
+        // A custom signet mined at regtest difficulty sets its own.
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        let custom_limit = Network::Regtest.pow_limit();
+        verifier.initialize(custom_genesis, 0, SIGNET_GENESIS_TIMESTAMP, SIGNET, custom_limit, 0, 0).unwrap();
+        assert_eq!(verifier.pow_limit().unwrap(), custom_limit);
+        assert_eq!(verifier.submit_header(easy_header).unwrap(), 1);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_custom_retarget_interval() {
+        // Below regtest's limit, which is too close to 2^256 to scale by the timespan.
+        const BITS: u32 = 0x1f7fffff;
+        let genesis = mine_header(&"00".repeat(32), SIGNET_GENESIS_TIMESTAMP, BITS);
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        let pow_limit = Network::Regtest.pow_limit();
+        verifier.initialize(genesis.clone(), 0, SIGNET_GENESIS_TIMESTAMP, SIGNET, pow_limit, 10, 600).unwrap();
+
+        let mut prev = block_hash(&genesis);
+        for height in 1..10 {
+            let header = mine_header(&prev, SIGNET_GENESIS_TIMESTAMP + height, BITS);
+            assert_eq!(verifier.submit_header(header.clone()).unwrap(), height);
+            prev = block_hash(&header);
+        }
+
+        // Nine seconds for an epoch meant to take ten minutes: height 10 retargets.
+        let timestamp = SIGNET_GENESIS_TIMESTAMP + 10;
+        let stale = mine_header(&prev, timestamp, BITS);
+        assert_eq!(verifier.submit_header(stale), Err(VerifyError::BadDifficulty.into()));
+        let bits = verifier.predict_next_bits(SIGNET_GENESIS_TIMESTAMP + 9).unwrap();
+        assert_ne!(bits, BITS);
+        assert_eq!(verifier.submit_header(mine_header(&prev, timestamp, bits)).unwrap(), 10);
+        assert_eq!(verifier.retarget_anchor().unwrap(), (10, timestamp));
+        assert_eq!(verifier.difficulty_epoch(25), 2);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_fork_retargets_across_crossed_boundary() {
+        const BITS: u32 = 0x1f7fffff;
+        const T0: u32 = SIGNET_GENESIS_TIMESTAMP;
+        let pow_limit = Network::Regtest.pow_limit();
+        let genesis = mine_header(&"00".repeat(32), T0, BITS);
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(genesis.clone(), 0, T0, SIGNET, pow_limit, 10, 600).unwrap();
+
+        // Branch a crosses the boundary at 10 first; both epochs are fast enough to
+        // clamp, so block 10 has the same bits on either branch.
+        let fast_bits = pow::next_bits(BITS, T0, T0 + 9, 600, pow_limit).unwrap();
+        let mut a = vec![block_hash(&genesis)];
+        for height in 1..=11 {
+            let bits = if height < 10 { BITS } else { fast_bits };
+            let header = mine_header(&a[height as usize - 1], T0 + height, bits);
+            verifier.submit_header(header.clone()).unwrap();
+            a.push(block_hash(&header));
+        }
+        assert_eq!(verifier.retarget_anchor().unwrap(), (10, T0 + 10));
+
+        // Branch b forks at 5 and reaches 10 after the tip has left that epoch.
+        let (mut prev, mut b10_timestamp) = (a[5].clone(), 0);
+        for height in 6..=12 {
+            let (timestamp, bits) = match height {
+                6..=9 => (T0 + 100 + height, BITS),
+                _ => (T0 + 1000 + 30 * (height - 10), fast_bits),
+            };
+            let header = mine_header(&prev, timestamp, bits);
+            assert_eq!(verifier.submit_header_fork(header.clone()).unwrap(), height);
+            prev = block_hash(&header);
+            if height == 10 {
+                b10_timestamp = timestamp;
+            }
+            let expected_tip = if height < 12 { &a[11] } else { &prev };
+            assert_eq!(encode_display_hash(&verifier.tip_hash.get().0), *expected_tip);
+        }
+        assert_eq!(verifier.latest_height().unwrap(), 12);
+        assert_eq!(verifier.retarget_anchor().unwrap(), (10, b10_timestamp));
+
+        // The next retarget measures b's own epoch, not the one a started at 10.
+        for height in 13..20 {
+            let header = mine_header(&prev, b10_timestamp + 30 * (height - 10), fast_bits);
+            verifier.submit_header(header.clone()).unwrap();
+            prev = block_hash(&header);
+        }
+        let b19_timestamp = b10_timestamp + 270;
+        let expected = pow::next_bits(fast_bits, b10_timestamp, b19_timestamp, 600, pow_limit).unwrap();
+        let from_a = pow::next_bits(fast_bits, T0 + 10, b19_timestamp, 600, pow_limit).unwrap();
+        assert_ne!(expected, from_a);
+        assert_eq!(verifier.predict_next_bits(b19_timestamp), Ok(expected));
+        let stale = mine_header(&prev, b19_timestamp + 30, from_a);
+        assert_eq!(verifier.submit_header(stale), Err(VerifyError::BadDifficulty.into()));
+        assert_eq!(verifier.submit_header(mine_header(&prev, b19_timestamp + 30, expected)).unwrap(), 20);
+    }
+
+    #[test]
+    fn test_verify_signet_signature() {
+        // A block on top of the signet genesis, signed for a 1-of-2 multisig challenge
+        // whose second key is ours. Checked against libbitcoinconsensus when generated.
+        let header = "00000020f61eee3b63a380a477a063af32b2bbc97c9ff9f01f2c4225e973988108000000cd0a37117cdfc0f0480a012adf68de6e9118bd09a31fa50a0d65307f8e4342f358914d5fae77031e00000000";
+        let challenge = "512103ad5e0edad18cb1f0fc0d28a3d4f1f3e445640337489abb10404f2d1e086be4302102d718be1a6252d47b4980deeceade019d41670ab750a5211bfc1b9dd3aadba36b52ae";
+        let solution = "490047304402200a8bfcf5dbe1ea7f3f5ef50d5815c61f1102ce3cac20a7ac8b4795ce32ea5f020220434fb9dd764014f327a52d6eab0d6df1b1e614a53fb82fada57700297c77baee0100";
+
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        assert!(verifier.verify_signet_signature(header.into(), challenge.into(), solution.into()).unwrap());
+
+        // The signature commits to the block's time, but not to its nonce.
+        let retimed = header.replace("58914d5f", "59914d5f");
+        assert!(!verifier.verify_signet_signature(retimed, challenge.into(), solution.into()).unwrap());
+        let renonced = format!("{}01000000", &header[..152]);
+        assert!(verifier.verify_signet_signature(renonced, challenge.into(), solution.into()).unwrap());
+
+        // Witness data is not allowed for a bare challenge.
+        let with_witness = format!("{}0100", &solution[..solution.len() - 2]);
+        assert!(!verifier.verify_signet_signature(header.into(), challenge.into(), with_witness).unwrap());
+
+        // OP_TRUE accepts anything; scripts outside the supported templates revert.
+        assert!(verifier.verify_signet_signature(header.into(), "51".into(), "0000".into()).unwrap());
+        let p2wpkh = format!("0014{}", "11".repeat(20));
+        let result = verifier.verify_signet_signature(header.into(), p2wpkh, "0000".into());
+        assert_eq!(result, Err(VerifyError::UnsupportedScript.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_export_import_state_round_trip() {
+        let owner = Address::from([0x11; 20]);
+        let old_vm = TestVM::default();
+        let mut old = genesis_relay(&old_vm);
+        old.submit_headers(MAINNET_HEADERS.iter().map(|h| h.to_string()).collect()).unwrap();
+        let state = old.export_state().unwrap();
+        let (tip_height, tip_hash, chainwork, anchor_height, anchor_timestamp) = state.clone();
+        assert_eq!(tip_height, 5);
+        assert_eq!(tip_hash, block_hash(MAINNET_HEADERS[4]));
+        // Six blocks of 0x1d00ffff work each.
+        assert_eq!(chainwork, format!("{:064x}", 6 * 0x1_0001_0001u64));
+
+        // The new deployment is checkpointed at genesis, then takes over the old tip.
+        let new_vm = TestVM::default();
+        new_vm.set_sender(owner);
+        let mut new = genesis_relay(&new_vm);
+        let result = new.import_state(
+            tip_height,
+            tip_hash.clone(),
+            chainwork.clone(),
+            anchor_height,
+            anchor_timestamp,
+            MAINNET_HEADERS[3].into(),
+        );
+        assert_eq!(result, Err(VerifyError::HashMismatch.into()));
+        new.import_state(tip_height, tip_hash.clone(), chainwork.clone(), anchor_height, anchor_timestamp, MAINNET_HEADERS[4].into())
+            .unwrap();
+        assert_eq!(new.export_state().unwrap(), state);
+        assert_eq!(new.get_header(5).unwrap(), MAINNET_HEADERS[4]);
+        assert_eq!(new.sync_cursor(), 5);
+
+        // Only the owner may import, and a tip that isn't stored needs its header.
+        new_vm.set_sender(Address::from([0x22; 20]));
+        let result = new.import_state(tip_height, tip_hash, chainwork.clone(), anchor_height, anchor_timestamp, String::new());
+        assert_eq!(result, Err(VerifyError::NotOwner.into()));
+        new_vm.set_sender(owner);
+        let unknown = block_hash(MAINNET_HEADERS[3]);
+        let result = new.import_state(4, unknown, chainwork, anchor_height, anchor_timestamp, String::new());
+        assert_eq!(result, Err(VerifyError::UnknownBlock.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_import_shorter_state() {
+        let source_vm = TestVM::default();
+        let mut source = genesis_relay(&source_vm);
+        source.submit_headers(MAINNET_HEADERS[..2].iter().map(|h| h.to_string()).collect()).unwrap();
+        let (tip_height, tip_hash, chainwork, anchor_height, anchor_timestamp) = source.export_state().unwrap();
+
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        verifier.submit_headers(MAINNET_HEADERS.iter().map(|h| h.to_string()).collect()).unwrap();
+        let import = |verifier: &mut BtcVerifier| {
+            let tip_header = MAINNET_HEADERS[1].into();
+            verifier.import_state(tip_height, tip_hash.clone(), chainwork.clone(), anchor_height, anchor_timestamp, tip_header)
+        };
+        // The synced chain has to be pruned down to its tip first.
+        assert_eq!(import(&mut verifier), Err(VerifyError::ChainNotEmpty.into()));
+        verifier.prune_headers(5).unwrap();
+        import(&mut verifier).unwrap();
+
+        // Nothing of the longer chain is left at the heights around the new tip.
+        assert_eq!(verifier.canonical_hash_at.get(5), FixedBytes::ZERO);
+        assert!(!verifier.header_exists(1));
+        assert_eq!(verifier.get_header(2).unwrap(), MAINNET_HEADERS[1]);
+        assert_eq!(verifier.chain_stats().unwrap(), (2, 2, 1));
+
+        // So the cleared blocks can be synced again.
+        verifier.submit_headers(MAINNET_HEADERS[2..].iter().map(|h| h.to_string()).collect()).unwrap();
+        assert_eq!(verifier.chain_stats().unwrap(), (2, 5, 4));
+        assert!(verifier.audit_chain(2, 4).unwrap());
+
+        // A stale fork header can't be pruned, so it rules out importing for good.
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        verifier.submit_header(a1.clone()).unwrap();
+        verifier.submit_header_fork(mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS)).unwrap();
+        verifier.prune_headers(1).unwrap();
+        assert_eq!(verifier.chain_stats().unwrap(), (1, 1, 2));
+        let (tip_height, tip_hash, chainwork, anchor_height, anchor_timestamp) = verifier.export_state().unwrap();
+        let result = verifier.import_state(tip_height, tip_hash, chainwork, anchor_height, anchor_timestamp, String::new());
+        assert_eq!(result, Err(VerifyError::ChainNotEmpty.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_set_tip_for_confirmation_depth() {
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(BLOCK_702861_HEADER.into(), 702861, 1633343323, MAINNET, U256::ZERO, 0, 0).unwrap();
+
+        let txids: Vec<[u8; 32]> = BLOCK_702861_TXIDS.chunks(32).map(|txid| txid.try_into().unwrap()).collect();
+        let index = 1234;
+        let txid = encode_display_hash(&txids[index]);
+        let proof = merkle_branch(&txids, index);
+        assert_eq!(proof.len(), 12);
+        let result = verifier.verify_tx_at_height(txid.clone(), 702861, proof.clone(), index as u32, 6);
+        assert_eq!(result, Err(VerifyError::InsufficientConfirmations.into()));
+
+        // Jump the tip five blocks ahead instead of mining them.
+        verifier.test_set_tip(702866, "22".repeat(32), format!("{:064x}", 1u64 << 40)).unwrap();
+        assert_eq!(verifier.latest_height().unwrap(), 702866);
+        assert!(verifier.verify_tx_at_height(txid, 702861, proof.clone(), index as u32, 6).unwrap());
+        let other = encode_display_hash(&txids[index + 1]);
+        assert!(!verifier.verify_tx_at_height(other, 702861, proof, index as u32, 6).unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_contains_txs() {
+        let vm = TestVM::default();
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP);
+        let mut verifier = BtcVerifier::from(&vm);
+        verifier.initialize(BLOCK_702861_HEADER.into(), 702861, 1633343323, MAINNET, U256::ZERO, 0, 0).unwrap();
+
+        let txids: Vec<[u8; 32]> = BLOCK_702861_TXIDS.chunks(32).map(|txid| txid.try_into().unwrap()).collect();
+        let indices = vec![7, 1234, 1234];
+        let proofs = indices.iter().map(|&index| merkle_branch(&txids, index as usize)).collect::<Vec<_>>();
+        // The third claims the 1234th proof for a different transaction.
+        let claimed = [7, 1234, 1235].map(|index| encode_display_hash(&txids[index])).to_vec();
+        let results = verifier.contains_txs(702861, claimed.clone(), proofs.clone(), indices.clone()).unwrap();
+        assert_eq!(results, vec![true, true, false]);
+
+        assert_eq!(
+            verifier.contains_txs(702861, claimed[..2].to_vec(), proofs.clone(), indices.clone()),
+            Err(VerifyError::LengthMismatch.into())
+        );
+        assert_eq!(verifier.contains_txs(702862, claimed, proofs, indices), Err(VerifyError::UnknownBlock.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_get_header_bytes() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        verifier.submit_header(MAINNET_HEADERS[0].into()).unwrap();
+
+        for height in 0..=1 {
+            let bytes = verifier.get_header_bytes(height).unwrap();
+            assert_eq!(bytes.len(), 80);
+            assert_eq!(hex::encode(&bytes), verifier.get_header(height).unwrap());
+            let hash = verifier.hash_btc_header_bytes(bytes).unwrap();
+            assert_eq!(hex::encode(hash), verifier.hash_btc_header(verifier.get_header(height).unwrap()).unwrap());
+        }
+        assert_eq!(verifier.get_header_bytes(2), Err(VerifyError::UnknownBlock.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_duplicate_header_rejected() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        let b1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        verifier.submit_header(a1.clone()).unwrap();
+        verifier.submit_header_fork(b1.clone()).unwrap();
+        let state = verifier.export_state().unwrap();
+        let b1_work = verifier.chainwork.get(FixedBytes::from(decode_display_hash(&block_hash(&b1)).unwrap()));
+
+        // Resubmitting either block, the tip or the stale one, changes nothing.
+        for header in [&a1, &b1] {
+            let result = verifier.submit_header_fork(header.clone());
+            assert_eq!(result, Err(VerifyError::DuplicateHeader.into()));
+        }
+        assert_eq!(verifier.submit_header(REGTEST_GENESIS_HEADER.into()), Err(VerifyError::PrevHashMismatch.into()));
+        assert_eq!(verifier.export_state().unwrap(), state);
+        assert_eq!(verifier.chainwork.get(FixedBytes::from(decode_display_hash(&block_hash(&b1)).unwrap())), b1_work);
+    }
+
+    #[test]
+    fn test_address_to_script() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let cases = [
+            ("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", "76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac"),
+            ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", "a914b472a266d0bd89c13706a4132ccfb16f7c3b9fcb87"),
+            ("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", "0014e8df018c7e326cc253faac7e46cdc51e68542c42"),
+            (
+                "bc1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3qccfmv3",
+                "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262",
+            ),
+            (
+                "bc1p5d7rjq7g6rdk2yhzks9smlaqtedr4dekq08ge8ztwac72sfr9rusxg3297",
+                "5120a37c3903c8d0db6512e2b40b0dffa05e5a3ab73603ce8c9c4b7771e5412328f9",
+            ),
+            ("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", "76a914243f1394f44554f4ce3fd68649c19adc483ce92488ac"),
+            ("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc", "a9144e9f39ca4688ff102128ea4ccda34105324305b087"),
+            ("TB1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KXPJZSX", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+            ("bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+        ];
+        for (address, script) in cases {
+            assert_eq!(verifier.address_to_script(address.into()).unwrap(), script, "{address}");
+        }
+
+        let invalid = [
+            // Bad Base58Check checksum.
+            "1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb",
+            // Bad bech32 checksum, and mixed case.
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdr",
+            "bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5MDQ",
+            // A taproot program checksummed with bech32 instead of bech32m (BIP350).
+            "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+            "",
+        ];
+        for address in invalid {
+            assert_eq!(verifier.address_to_script(address.into()), Err(VerifyError::InvalidAddress.into()), "{address}");
+        }
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_reorg_detected_event() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        let a2 = mine_header(&block_hash(&a1), REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        verifier.submit_header(a1.clone()).unwrap();
+        verifier.submit_header(a2.clone()).unwrap();
+
+        // Extending the tip through the fork path, or building a lighter fork, isn't a reorg.
+        let a3 = mine_header(&block_hash(&a2), REGTEST_GENESIS_TIMESTAMP + 3, REGTEST_BITS);
+        verifier.submit_header_fork(a3.clone()).unwrap();
+        let b1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 100, REGTEST_BITS);
+        let b2 = mine_header(&block_hash(&b1), REGTEST_GENESIS_TIMESTAMP + 101, REGTEST_BITS);
+        let b3 = mine_header(&block_hash(&b2), REGTEST_GENESIS_TIMESTAMP + 102, REGTEST_BITS);
+        let b4 = mine_header(&block_hash(&b3), REGTEST_GENESIS_TIMESTAMP + 103, REGTEST_BITS);
+        for header in [&b1, &b2, &b3] {
+            verifier.submit_header_fork(header.clone()).unwrap();
+        }
+        assert!(vm.get_emitted_logs().is_empty());
+
+        // b4 outweighs a3, rewinding a3, a2 and a1 back to genesis.
+        verifier.submit_header_fork(b4.clone()).unwrap();
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        let (topics, data) = &logs[0];
+        assert_eq!(topics[0], ReorgDetected::SIGNATURE_HASH);
+        let event = ReorgDetected::decode_raw_log(topics.iter().copied(), data).unwrap();
+        assert_eq!(event.oldHeight, 3);
+        assert_eq!(event.oldTip.0, decode_display_hash(&block_hash(&a3)).unwrap());
+        assert_eq!(event.newHeight, 4);
+        assert_eq!(event.newTip.0, decode_display_hash(&block_hash(&b4)).unwrap());
+        assert_eq!(event.depth, U256::from(3));
+    }
+
+    #[test]
+    fn test_verify_pow_against_target() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let genesis_target = format!("00000000ffff{}", "00".repeat(26));
+        assert!(verifier.verify_pow_against_target(GENESIS_HEADER.into(), genesis_target).unwrap());
+
+        // The hash itself is the hardest target it meets.
+        let hash = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+        assert!(verifier.verify_pow_against_target(GENESIS_HEADER.into(), hash.into()).unwrap());
+        let below = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26e";
+        assert!(!verifier.verify_pow_against_target(GENESIS_HEADER.into(), below.into()).unwrap());
+
+        assert_eq!(
+            verifier.verify_pow_against_target(GENESIS_HEADER.into(), "ffff".into()),
+            Err(VerifyError::InvalidLength.into())
+        );
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_chain_stats_after_pruning() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        for header in MAINNET_HEADERS {
+            verifier.submit_header(header.into()).unwrap();
+        }
+        assert_eq!(verifier.chain_stats().unwrap(), (0, 5, 6));
+
+        let owner = verifier.owner();
+        vm.set_sender(Address::repeat_byte(0x99));
+        assert_eq!(verifier.prune_headers(3), Err(VerifyError::NotOwner.into()));
+        vm.set_sender(owner);
+        assert_eq!(verifier.prune_headers(6), Err(VerifyError::InvalidPruneHeight.into()));
+
+        assert_eq!(verifier.prune_headers(3).unwrap(), 3);
+        assert_eq!(verifier.chain_stats().unwrap(), (3, 5, 3));
+        assert!(!verifier.header_exists(2));
+        assert_eq!(verifier.get_header(3).unwrap(), MAINNET_HEADERS[2]);
+        assert_eq!(verifier.prune_headers(2).unwrap(), 0);
+
+        // The chain keeps growing on the pruned relay.
+        assert_eq!(verifier.prune_headers(5).unwrap(), 2);
+        assert_eq!(verifier.chain_stats().unwrap(), (5, 5, 1));
+        assert_eq!(verifier.tip_header_parsed().unwrap().0, 1);
+    }
+
+    #[test]
+    fn test_normalize_pubkey() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        // The keys for secret keys 1 (even y) and 6 (odd y).
+        let keys = [
+            (
+                "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+                "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8",
+            ),
+            (
+                "03fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a1460297556",
+                "04fff97bd5755eeea420453a14355235d382f6472f8568a18b2f057a1460297556ae12777aacfbb620f3be96017f45c560de80f0f6518fe4a03c870c36b075f297",
+            ),
+        ];
+        for (compressed, uncompressed) in keys {
+            assert_eq!(verifier.normalize_pubkey(compressed.into(), false).unwrap(), uncompressed);
+            assert_eq!(verifier.normalize_pubkey(uncompressed.into(), true).unwrap(), compressed);
+            assert_eq!(verifier.normalize_pubkey(compressed.into(), true).unwrap(), compressed);
+            assert_eq!(verifier.normalize_pubkey(uncompressed.into(), false).unwrap(), uncompressed);
+        }
+
+        // Flipping the last byte of y moves the point off the curve.
+        let (_, uncompressed) = keys[0];
+        let off_curve = format!("{}b9", &uncompressed[..128]);
+        assert_eq!(verifier.normalize_pubkey(off_curve, true), Err(VerifyError::InvalidPublicKey.into()));
+        // x = 5 has no y on secp256k1.
+        let no_y = format!("02{}05", "00".repeat(31));
+        assert_eq!(verifier.normalize_pubkey(no_y, false), Err(VerifyError::InvalidPublicKey.into()));
+        assert_eq!(verifier.normalize_pubkey("79be667e".into(), true), Err(VerifyError::InvalidLength.into()));
+    }
+
+    #[test]
+    fn test_compute_merkle_root_from_proof() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let txids: Vec<[u8; 32]> = BLOCK_702861_TXIDS.chunks(32).map(|txid| txid.try_into().unwrap()).collect();
+        let root = "407d72768cec1a244b7599af79f554055c72d6b2356c890f8c25abf797679022";
+
+        for index in [0, 1, 1234, txids.len() - 1] {
+            let leaf = encode_display_hash(&txids[index]);
+            let proof = merkle_branch(&txids, index);
+            let computed = verifier.compute_merkle_root_from_proof(leaf.clone(), proof.clone(), index as u32).unwrap();
+            assert_eq!(computed, root);
+            assert!(verifier.verify_merkle_proof(leaf, computed, proof, index as u32).unwrap());
+        }
+
+        // The same branch at another position leads somewhere else.
+        let proof = merkle_branch(&txids, 5);
+        let leaf = encode_display_hash(&txids[5]);
+        assert_ne!(verifier.compute_merkle_root_from_proof(leaf.clone(), proof.clone(), 4).unwrap(), root);
+        let too_far = 1 << proof.len();
+        assert_eq!(verifier.compute_merkle_root_from_proof(leaf, proof, too_far), Err(VerifyError::InvalidLength.into()));
+    }
+
+    #[test]
+    fn test_sighash_validation() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        // BIP143's native P2WPKH example: input 1 spends 6 BTC.
+        let tx = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000";
+        let script_code = "76a9141d0f172a0ecb48aee1be1f2687d2963ae33f71a188ac";
+        let segwit = |tx: &str, index, sighash_type| {
+            verifier.sighash_segwit_v0(tx.into(), index, script_code.into(), 600_000_000, sighash_type)
+        };
+        let legacy = |tx: &str, index, sighash_type| verifier.sighash_legacy(tx.into(), index, script_code.into(), sighash_type);
+
+        assert_eq!(segwit(tx, 1, 0x01).unwrap(), "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670");
+        assert_eq!(segwit(tx, 1, 0x82).unwrap(), "4abb5ef58a968f8e1ab88a9fb72f2ce74b3022e65d334ac7b8aeda747515dc15");
+        assert_eq!(segwit(tx, 1, 0x83).unwrap(), "79ff9ff708f79ce8f7a4f90d62028533a99d7340b7fb3d819dfd9a599a78e39c");
+        assert_eq!(legacy(tx, 0, 0x01).unwrap(), "47194bc3c303a30aa5f78e45c7c2980b3be1284a9d69b1ea9ec0d29aac5f6848");
+        assert_eq!(legacy(tx, 1, 0x03).unwrap(), "33cd468bd6b82f04bcef180b748c521d6fdee3b11711a2f27b2e465915afaec2");
+
+        // With the second output dropped, SINGLE on input 1 has no output to sign:
+        // legacy hashes to one, segwit v0 commits to no outputs instead.
+        let one_output = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff01202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac11000000";
+        let one = format!("01{}", "00".repeat(31));
+        assert_eq!(legacy(one_output, 1, 0x03).unwrap(), one);
+        assert_eq!(legacy(one_output, 1, 0x83).unwrap(), one);
+        assert_eq!(legacy(one_output, 0, 0x03).unwrap(), "0d8ad17ba098be7eaf7efff778bb22e234805b5d370c996271a7f5ff7416f263");
+        assert_eq!(segwit(one_output, 1, 0x03).unwrap(), "471a6e7963aa0c328ee12392fb1a345148edf326b4223660e1a770fdc2826435");
+
+        for bad_type in [0x00, 0x04, 0x80, 0x84, 0x41, 0x101] {
+            assert_eq!(legacy(tx, 0, bad_type), Err(VerifyError::BadSighashType.into()));
+            assert_eq!(segwit(tx, 0, bad_type), Err(VerifyError::BadSighashType.into()));
+        }
+        assert_eq!(legacy(tx, 2, 0x01), Err(VerifyError::InputOutOfRange.into()));
+        assert_eq!(segwit(tx, 2, 0x01), Err(VerifyError::InputOutOfRange.into()));
+    }
+
+    #[test]
+    fn test_verify_p2wpkh_spend() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        // Tx 7b0f5631...6cf8 in block 702861 spends a P2WPKH output created earlier
+        // in the same block.
+        let tx = "020000000001014768e5ce8096f40814ec466689063a910aa81d70a98912f9d4c74b3dbaf476db0600000000fdffffff01b3698713000000001600144068add9d8059196e4758bc2b017b02446fe146b02473044022034763889ef9a20477e108a4d2ddb3a89b473409b4996dad886172dbd879be0410220563b77ca4b480d1ea64e36fb5b06d8eb615523956c345726400e5f390db4c061012102034d44f740dbf6295431c1bf5c8ae561858b3403a17ade8e224cf7fa5bcdf47500000000";
+        let amount = 327_644_613;
+        let pubkey = "02034d44f740dbf6295431c1bf5c8ae561858b3403a17ade8e224cf7fa5bcdf475";
+        let sig = "3044022034763889ef9a20477e108a4d2ddb3a89b473409b4996dad886172dbd879be0410220563b77ca4b480d1ea64e36fb5b06d8eb615523956c345726400e5f390db4c06101";
+        // The spent output's witness program is the key's Hash160.
+        assert_eq!(hex::encode(hash::hash160(&hex::decode(pubkey).unwrap())), "5dff7a05c5ceda42a53e929ad2e2ef4b82cbd83e");
+
+        let spend = |amount, pubkey: &str, sig: &str| verifier.verify_p2wpkh_spend(tx.into(), 0, amount, pubkey.into(), sig.into());
+        assert!(spend(amount, pubkey, sig).unwrap());
+        // The signature commits to the amount and to the key's script code.
+        assert!(!spend(amount + 1, pubkey, sig).unwrap());
+        let other_key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        assert!(!spend(amount, other_key, sig).unwrap());
+        let with_none = format!("{}02", &sig[..sig.len() - 2]);
+        assert!(!spend(amount, pubkey, &with_none).unwrap());
+        let bad_type = format!("{}04", &sig[..sig.len() - 2]);
+        assert_eq!(spend(amount, pubkey, &bad_type), Err(VerifyError::BadSighashType.into()));
+        assert_eq!(
+            verifier.verify_p2wpkh_spend(tx.into(), 1, amount, pubkey.into(), sig.into()),
+            Err(VerifyError::InputOutOfRange.into())
+        );
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_canonical_hash_at_follows_reorgs() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let canonical = |verifier: &BtcVerifier, height| encode_display_hash(&verifier.canonical_hash_at.get(height).0);
+
+        let mut a = vec![genesis.clone()];
+        for height in 1..=3 {
+            let header = mine_header(&a[height - 1], REGTEST_GENESIS_TIMESTAMP + height as u32, REGTEST_BITS);
+            verifier.submit_header(header.clone()).unwrap();
+            a.push(block_hash(&header));
+        }
+        // A fork from block 1 overtakes the a-chain at height 4.
+        let mut b = vec![a[1].clone()];
+        for height in 2..=4 {
+            let header = mine_header(&b[height - 2], REGTEST_GENESIS_TIMESTAMP + 100 + height as u32, REGTEST_BITS);
+            verifier.submit_header_fork(header.clone()).unwrap();
+            b.push(block_hash(&header));
+        }
+        assert_eq!(verifier.latest_height().unwrap(), 4);
+        for height in 0..=1 {
+            assert_eq!(canonical(&verifier, height), a[height as usize]);
+        }
+        for height in 2..=4 {
+            assert_eq!(canonical(&verifier, height), b[height as usize - 1]);
+            assert!(verifier.is_canonical_block(b[height as usize - 1].clone()).unwrap());
+        }
+        for stale in &a[2..] {
+            assert!(!verifier.is_canonical_block(stale.clone()).unwrap());
+        }
+        assert_eq!(block_hash(&verifier.get_header(2).unwrap()), b[1]);
+
+        // Back to the a-chain, now two blocks longer; b's heights are rewritten again.
+        let mut prev = a[3].clone();
+        for height in 4..=5 {
+            let header = mine_header(&prev, REGTEST_GENESIS_TIMESTAMP + height, REGTEST_BITS);
+            verifier.submit_header_fork(header.clone()).unwrap();
+            prev = block_hash(&header);
+            a.push(prev.clone());
+        }
+        for height in 0..=5 {
+            assert_eq!(canonical(&verifier, height), a[height as usize]);
+        }
+        assert!(!verifier.is_canonical_block(b[3].clone()).unwrap());
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_height_views_follow_reorg() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let a1 = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 1, REGTEST_BITS);
+        let a2 = mine_header(&block_hash(&a1), REGTEST_GENESIS_TIMESTAMP + 2, REGTEST_BITS);
+        verifier.submit_headers(vec![a1.clone(), a2]).unwrap();
+
+        let mut b = vec![];
+        let mut prev = genesis;
+        for height in 1..=3 {
+            let header = mine_header(&prev, REGTEST_GENESIS_TIMESTAMP + 100 + height, REGTEST_BITS);
+            verifier.submit_header_fork(header.clone()).unwrap();
+            prev = block_hash(&header);
+            b.push(header);
+        }
+        assert_eq!(verifier.latest_height().unwrap(), 3);
+        assert_eq!(verifier.sync_cursor(), 3);
+
+        // Every height-indexed view answers from the new branch.
+        assert_eq!(verifier.get_header(1).unwrap(), b[0]);
+        assert_eq!(verifier.get_header_bytes(2).unwrap(), hex::decode(&b[1]).unwrap());
+        assert!(verifier.header_exists(3));
+        assert!(verifier.audit_chain(0, 4).unwrap());
+        // Each mined block holds a single transaction, so its txid is the Merkle root.
+        let txid = |header: &str| verifier.parse_header(header.into()).unwrap().2;
+        assert!(verifier.verify_tx_at_height(txid(&b[0]), 1, vec![], 0, 3).unwrap());
+        assert!(!verifier.verify_tx_at_height(txid(&a1), 1, vec![], 0, 3).unwrap());
+        let results = verifier.contains_txs(1, vec![txid(&b[0]), txid(&a1)], vec![vec![], vec![]], vec![0, 0]);
+        assert_eq!(results, Ok(vec![true, false]));
+
+        // Pruning clears the best chain's heights and leaves the stale branch stored.
+        assert_eq!(verifier.chain_stats().unwrap(), (0, 3, 6));
+        assert_eq!(verifier.prune_headers(2).unwrap(), 2);
+        assert!(!verifier.is_known_block(block_hash(&b[0])).unwrap());
+        assert!(verifier.is_known_block(block_hash(&a1)).unwrap());
+        assert_eq!(verifier.chain_stats().unwrap(), (2, 3, 4));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_reorg_depth_limit() {
+        // Builds a tip at `depth - 1` and a branch from genesis up to `depth - 1`, then
+        // submits the branch's block at `depth`, which overtakes the tip.
+        fn reorg(vm: &TestVM, depth: u32) -> (BtcVerifier, Vec<String>, Vec<String>) {
+            let mut verifier = regtest_relay(vm);
+            let genesis = block_hash(REGTEST_GENESIS_HEADER);
+            let (mut old, mut new) = (vec![genesis.clone()], vec![genesis]);
+            for height in 1..depth {
+                let header = mine_header(old.last().unwrap(), REGTEST_GENESIS_TIMESTAMP + height, REGTEST_BITS);
+                verifier.submit_header(header.clone()).unwrap();
+                old.push(block_hash(&header));
+            }
+            for height in 1..=depth {
+                let header = mine_header(new.last().unwrap(), REGTEST_GENESIS_TIMESTAMP + 1000 + height, REGTEST_BITS);
+                assert_eq!(verifier.submit_header_fork(header.clone()), Ok(height));
+                new.push(block_hash(&header));
+            }
+            (verifier, old, new)
+        }
+
+        // Below the per-call limit the reorg completes in the call that causes it.
+        let vm = TestVM::default();
+        let (verifier, _, new) = reorg(&vm, MAX_ANCESTOR_DEPTH + 1);
+        assert!(!verifier.reorg_pending());
+        assert_eq!(verifier.audit_chain(0, MAX_ANCESTOR_DEPTH), Ok(true));
+        assert!(verifier.is_canonical_block(new[1].clone()).unwrap());
+
+        // 2 * 144 + 9 heights to re-index take three calls in all.
+        let depth = 2 * MAX_ANCESTOR_DEPTH + 10;
+        let vm = TestVM::default();
+        let (mut verifier, old, new) = reorg(&vm, depth);
+        let pending: Vec<u8> = VerifyError::ReorgPending.into();
+        assert!(verifier.reorg_pending());
+        assert_eq!(verifier.latest_height(), Ok(depth));
+        assert_eq!(verifier.sync_cursor(), depth);
+        assert!(vm.get_emitted_logs().is_empty());
+
+        // The tip and the heights already re-indexed are served; the rest wait.
+        assert_eq!(verifier.audit_chain(depth - MAX_ANCESTOR_DEPTH, MAX_ANCESTOR_DEPTH), Ok(true));
+        assert_eq!(verifier.get_header(1), Err(pending.clone()));
+        assert!(!verifier.header_exists(1));
+        assert_eq!(verifier.is_canonical_block(old[1].clone()), Err(pending.clone()));
+        assert_eq!(verifier.verify_tx_at_height(new[1].clone(), 1, vec![], 0, 1), Err(pending.clone()));
+        let late_fork = mine_header(&old[1], REGTEST_GENESIS_TIMESTAMP + 5000, REGTEST_BITS);
+        assert_eq!(verifier.submit_header_fork(late_fork), Err(pending.clone()));
+        assert_eq!(verifier.prune_headers(10), Err(pending));
+
+        // The new tip can still be extended while anyone finishes the reorg.
+        let next = mine_header(&new[depth as usize], REGTEST_GENESIS_TIMESTAMP + 2000, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(next.clone()), Ok(depth + 1));
+        assert_eq!(verifier.continue_reorg(), Ok(false));
+        assert_eq!(verifier.continue_reorg(), Ok(true));
+        assert!(!verifier.reorg_pending());
+        assert_eq!(verifier.continue_reorg(), Ok(true));
+
+        let logs = vm.get_emitted_logs();
+        assert_eq!(logs.len(), 1);
+        let event = ReorgDetected::decode_raw_log(logs[0].0.iter().copied(), &logs[0].1).unwrap();
+        assert_eq!((event.oldHeight, event.newHeight, event.depth), (depth - 1, depth + 1, U256::from(depth - 1)));
+        assert_eq!(event.newTip.0, decode_display_hash(&block_hash(&next)).unwrap());
+
+        assert_eq!(verifier.get_header(1).map(|header| block_hash(&header)), Ok(new[1].clone()));
+        assert!(!verifier.is_canonical_block(old[1].clone()).unwrap());
+        assert_eq!(verifier.audit_chain(0, MAX_ANCESTOR_DEPTH), Ok(true));
+        assert_eq!(verifier.audit_chain(MAX_ANCESTOR_DEPTH, MAX_ANCESTOR_DEPTH), Ok(true));
+        assert_eq!(verifier.audit_chain(2 * MAX_ANCESTOR_DEPTH, depth + 2 - 2 * MAX_ANCESTOR_DEPTH), Ok(true));
+    }
+
+    #[test]
+    fn test_tx_feerate() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        // Tx 7b0f5631...6cf8 from block 702861: 437 weight units, paying 3090 sat.
+        let segwit = "020000000001014768e5ce8096f40814ec466689063a910aa81d70a98912f9d4c74b3dbaf476db0600000000fdffffff01b3698713000000001600144068add9d8059196e4758bc2b017b02446fe146b02473044022034763889ef9a20477e108a4d2ddb3a89b473409b4996dad886172dbd879be0410220563b77ca4b480d1ea64e36fb5b06d8eb615523956c345726400e5f390db4c061012102034d44f740dbf6295431c1bf5c8ae561858b3403a17ade8e224cf7fa5bcdf47500000000";
+        assert_eq!(verifier.tx_vsize(segwit.into()).unwrap(), 110);
+        assert_eq!(verifier.tx_feerate(segwit.into(), vec![327_644_613]).unwrap(), 28);
+        assert_eq!(verifier.tx_feerate(segwit.into(), vec![327_641_523]).unwrap(), 0);
+        assert_eq!(verifier.tx_feerate(segwit.into(), vec![327_641_522]), Err(VerifyError::NegativeFee.into()));
+        assert_eq!(verifier.tx_feerate(segwit.into(), vec![1, 2]), Err(VerifyError::LengthMismatch.into()));
+
+        // Without witness data the vsize is just the length: BIP143's unsigned
+        // example is 160 bytes and spends 6.25 + 6 BTC into 3.3579 BTC.
+        let legacy = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c95a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac11000000";
+        assert_eq!(verifier.tx_vsize(legacy.into()).unwrap(), 160);
+        assert_eq!(verifier.tx_feerate(legacy.into(), vec![625_000_000, 600_000_000]).unwrap(), 5_557_562);
+        assert_eq!(verifier.tx_feerate(legacy.into(), vec![u64::MAX, 1]), Err(VerifyError::Overflow.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_empty_and_single_inputs() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        let empty: Vec<u8> = VerifyError::EmptyInput.into();
+
+        assert_eq!(verifier.merkle_root_from_txids(vec![]).unwrap_err(), empty);
+        assert_eq!(verifier.hash_btc_headers(vec![]).unwrap_err(), empty);
+        assert_eq!(verifier.verify_schnorr_batch(vec![], vec![], vec![]).unwrap_err(), empty);
+        assert_eq!(verifier.submit_headers(vec![]).unwrap_err(), empty);
+        assert_eq!(verifier.sync_cursor(), 0);
+
+        // One element behaves like the single-item method.
+        let txid = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        assert_eq!(verifier.merkle_root_from_txids(vec![txid.into()]).unwrap(), txid);
+        assert_eq!(
+            verifier.hash_btc_headers(vec![GENESIS_HEADER.into()]).unwrap(),
+            vec![verifier.hash_btc_header(GENESIS_HEADER.into()).unwrap()]
+        );
+        // BIP340 test vector 1.
+        let msg = "243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89";
+        let sig = "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de33418906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a";
+        let pubkey = "dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659";
+        assert!(verifier.verify_schnorr_batch(vec![msg.into()], vec![sig.into()], vec![pubkey.into()]).unwrap());
+        assert_eq!(verifier.submit_headers(vec![MAINNET_HEADERS[0].into()]).unwrap(), 1);
+        assert_eq!(verifier.sync_cursor(), 1);
+
+        let result = verifier.hash_btc_headers(vec![GENESIS_HEADER[2..].into()]);
+        assert_eq!(result, Err(VerifyError::InvalidLength.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_mid_epoch_bits_must_match_parent() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+
+        // Height 1 is mid-epoch, so it must keep the genesis bits.
+        let altered = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 600, 0x203f_ffff);
+        assert_eq!(verifier.submit_header(altered), Err(VerifyError::BadDifficulty.into()));
+        let header = mine_header(&genesis, REGTEST_GENESIS_TIMESTAMP + 600, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(header).unwrap(), 1);
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_future_timestamp_rejected() {
+        let vm = TestVM::default();
+        let mut verifier = regtest_relay(&vm);
+        assert_eq!(verifier.max_future_drift().unwrap(), 2 * 60 * 60);
+        let genesis = block_hash(REGTEST_GENESIS_HEADER);
+        let now = ARBITRUM_TIMESTAMP as u32;
+
+        let far_future = mine_header(&genesis, now + 30 * 24 * 60 * 60, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(far_future.clone()), Err(VerifyError::TimestampTooNew.into()));
+        let too_new = mine_header(&genesis, now + MAX_FUTURE_DRIFT + 1, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(too_new.clone()), Err(VerifyError::TimestampTooNew.into()));
+
+        // Exactly at the limit is fine, and the owner can widen it.
+        let at_limit = mine_header(&genesis, now + MAX_FUTURE_DRIFT, REGTEST_BITS);
+        assert_eq!(verifier.submit_header(at_limit.clone()).unwrap(), 1);
+        verifier.set_max_future_drift(MAX_FUTURE_DRIFT + 1).unwrap();
+        assert_eq!(verifier.submit_header_fork(too_new).unwrap(), 1);
+
+        vm.set_sender(Address::repeat_byte(0xbb));
+        assert_eq!(verifier.set_max_future_drift(0), Err(VerifyError::NotOwner.into()));
+    }
+
+    #[test]
+    fn test_relay_feature_gates_abi() {
+        use stylus_sdk::abi::Router;
+        use stylus_sdk::function_selector;
+
+        let vm = TestVM::default();
+        let mut verifier = BtcVerifier::from(&vm);
+        let mut route = |selector: [u8; 4], input: &[u8]| {
+            <BtcVerifier as Router<BtcVerifier>>::route(&mut verifier, u32::from_be_bytes(selector), input)
+        };
+
+        // The stateless methods are callable either way.
+        let mut input = [0u8; 32];
+        input[31] = 3;
+        let depth = route(function_selector!("merkleDepth", u32), &input).unwrap().unwrap();
+        assert_eq!(depth[31], 2);
+
+        // The relay's methods only exist with the feature.
+        let latest_height = route(function_selector!("latestHeight"), &[]);
+        if cfg!(feature = "relay") {
+            assert_eq!(latest_height, Some(Err(VerifyError::NotInitialized.into())));
+        } else {
+            assert_eq!(latest_height, None);
+        }
+    }
+
+    #[test]
+    fn test_hash256_bytes_matches_hex_method() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        for input in [GENESIS_HEADER, "", "68656c6c6f", BLOCK_702861_COINBASE] {
+            let hashed = verifier.hash256_bytes(hex::decode(input).unwrap());
+            assert_eq!(hex::encode(hashed), verifier.hash_btc_header(input.into()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_verify_header_chain_packed() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let packed = |headers: &[&str]| headers.iter().flat_map(|h| hex::decode(h).unwrap()).collect::<Vec<u8>>();
+
+        // Mainnet blocks 1 to 3.
+        let tip = verifier.verify_header_chain_packed(packed(&MAINNET_HEADERS[..3])).unwrap();
+        assert_eq!(tip, "0000000082b5015589a3fdf2d4baff403e6f0be035a5d9742c1cae6295464449");
+        assert_eq!(verifier.verify_header_chain_packed(packed(&MAINNET_HEADERS[..1])).unwrap(), block_hash(MAINNET_HEADERS[0]));
+
+        let mut truncated = packed(&MAINNET_HEADERS[..3]);
+        truncated.pop();
+        assert_eq!(verifier.verify_header_chain_packed(truncated), Err(VerifyError::InvalidLength.into()));
+        let result = verifier.verify_header_chain_packed(packed(&[MAINNET_HEADERS[0], MAINNET_HEADERS[2]]));
+        assert_eq!(result, Err(VerifyError::PrevHashMismatch.into()));
+        let mut unmined = packed(&MAINNET_HEADERS[..2]);
+        unmined[80 + 76] ^= 1;
+        assert_eq!(verifier.verify_header_chain_packed(unmined), Err(VerifyError::InsufficientPow.into()));
+        assert_eq!(verifier.verify_header_chain_packed(vec![]), Err(VerifyError::EmptyInput.into()));
+    }
+
+    #[cfg(feature = "relay")]
+    #[test]
+    fn test_seconds_since_last_submit() {
+        let vm = TestVM::default();
+        let mut verifier = genesis_relay(&vm);
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP + 600);
+        assert_eq!(verifier.seconds_since_last_submit(), 600);
+
+        verifier.submit_header(MAINNET_HEADERS[0].into()).unwrap();
+        assert_eq!(verifier.seconds_since_last_submit(), 0);
+        vm.set_block_timestamp(ARBITRUM_TIMESTAMP + 4200);
+        assert_eq!(verifier.seconds_since_last_submit(), 3600);
+
+        // A rejected header doesn't count as progress.
+        assert!(verifier.submit_header(MAINNET_HEADERS[2].into()).is_err());
+        assert_eq!(verifier.seconds_since_last_submit(), 3600);
+    }
+
+    #[test]
+    fn test_merkle_root_packed() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+
+        // 2500 txids, so several levels have an odd node to duplicate.
+        let root = verifier.merkle_root_packed(BLOCK_702861_TXIDS.to_vec()).unwrap();
+        let expected = verifier.merkle_root_from_txids(display_hashes(BLOCK_702861_TXIDS)).unwrap();
+        assert_eq!(encode_display_hash(&root.0), expected);
+        let single = verifier.merkle_root_packed(BLOCK_702861_TXIDS[..32].to_vec()).unwrap();
+        assert_eq!(single.as_slice(), &BLOCK_702861_TXIDS[..32]);
+
+        let result = verifier.merkle_root_packed(BLOCK_702861_TXIDS[..63].to_vec());
+        assert_eq!(result, Err(VerifyError::InvalidLength.into()));
+        assert_eq!(verifier.merkle_root_packed(vec![]), Err(VerifyError::EmptyInput.into()));
+    }
+
+    #[test]
+    fn test_require_valid_header_reverts_with_string() {
+        let vm = TestVM::default();
+        let verifier = BtcVerifier::from(&vm);
+        let reason = |result: Result<(), Vec<u8>>| Revert::abi_decode(&result.unwrap_err()).unwrap().reason;
+
+        assert_eq!(verifier.require_valid_header(GENESIS_HEADER.into()), Ok(()));
+        let mut unmined = hex::decode(GENESIS_HEADER).unwrap();
+        unmined[76] ^= 1;
+        let err = verifier.require_valid_header(hex::encode(&unmined)).unwrap_err();
+        assert_eq!(err[..4], [0x08, 0xc3, 0x79, 0xa0]);
+        assert_eq!(Revert::abi_decode(&err).unwrap().reason, "BTC: pow check failed");
+        assert_eq!(reason(verifier.require_valid_header("zz".into())), "BTC: header is not valid hex");
+        assert_eq!(reason(verifier.require_valid_header(GENESIS_HEADER[2..].into())), "BTC: header must be 80 bytes");
+    }
 }
@@ -0,0 +1,15 @@
+//! Caps on caller-controlled loop lengths.
+//!
+//! Methods check these on entry and revert with `TooLarge`, so an oversized input
+//! fails cheaply and predictably instead of running into the block gas limit.
+
+/// Most sibling hashes accepted in a Merkle proof. A proof's position is a `u32`,
+/// so no block can need more levels than this.
+pub const MAX_PROOF_LEN: usize = 32;
+
+/// Most items accepted by one batch call, such as `submit_headers`.
+pub const MAX_BATCH: usize = 64;
+
+/// Most blocks one call walks along a chain: the range `audit_chain` checks, or
+/// the heights one step of a reorg rewrites.
+pub const MAX_ANCESTOR_DEPTH: u32 = 144;
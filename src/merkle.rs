@@ -0,0 +1,62 @@
+//! Transaction Merkle trees.
+
+use alloc::vec::Vec;
+
+use crate::hash::hash256;
+
+/// Computes the Merkle root over `leaves` (internal byte order).
+///
+/// Levels with an odd number of nodes pair the last node with itself, as
+/// Bitcoin does. Returns `None` for an empty set of leaves.
+pub fn merkle_root(leaves: &[[u8; 32]]) -> Option<[u8; 32]> {
+    merkle_root_mutated(leaves).map(|(root, _)| root)
+}
+
+/// Like `merkle_root`, also reporting whether any level held two identical
+/// siblings. Such a list hashes to the same root as the one without its repeated
+/// tail (CVE-2012-2459), so Core's `BlockMerkleRoot` flags the block as mutated.
+pub fn merkle_root_mutated(leaves: &[[u8; 32]]) -> Option<([u8; 32], bool)> {
+    if leaves.is_empty() {
+        return None;
+    }
+
+    let mut level = leaves.to_vec();
+    let mut mutated = false;
+    while level.len() > 1 {
+        mutated |= level.chunks_exact(2).any(|pair| pair[0] == pair[1]);
+        level = level
+            .chunks(2)
+            .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+            .collect::<Vec<_>>();
+    }
+    Some((level[0], mutated))
+}
+
+/// Hashes two child nodes into their parent.
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(left);
+    buf[32..].copy_from_slice(right);
+    hash256(&buf)
+}
+
+/// Folds a Merkle branch into the root it commits to.
+///
+/// `proof` lists the siblings from the leaf upwards and `index` is the leaf's
+/// position in the block; each bit of `index` says whether the running hash is
+/// the right child at that level. Returns `None` if `index` has bits left over
+/// once the branch is used up, as it can't then be a position in the tree.
+pub fn root_from_proof(leaf: &[u8; 32], proof: &[[u8; 32]], mut index: u32) -> Option<[u8; 32]> {
+    let mut node = *leaf;
+    for sibling in proof {
+        node = if index & 1 == 1 { hash_pair(sibling, &node) } else { hash_pair(&node, sibling) };
+        index >>= 1;
+    }
+    (index == 0).then_some(node)
+}
+
+/// Number of sibling hashes in a Merkle proof for a block of `tx_count`
+/// transactions: `ceil(log2(tx_count))`, and zero for a coinbase-only block.
+pub fn depth(tx_count: u32) -> u32 {
+    u32::BITS - tx_count.saturating_sub(1).leading_zeros()
+}
@@ -0,0 +1,55 @@
+//! Consensus parameters the relay validates against.
+
+use stylus_sdk::alloy_primitives::U256;
+
+/// Number of blocks between difficulty adjustments.
+pub const RETARGET_INTERVAL: u32 = 2016;
+
+/// Time an epoch of `RETARGET_INTERVAL` blocks is expected to take: two weeks.
+pub const TARGET_TIMESPAN: u32 = 14 * 24 * 60 * 60;
+
+/// How far a header's timestamp may run ahead of the Arbitrum block's by default:
+/// two hours, Bitcoin's own limit against network-adjusted time.
+pub const MAX_FUTURE_DRIFT: u32 = 2 * 60 * 60;
+
+/// The Bitcoin networks a relay can follow, stored as their discriminant.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Network {
+    Mainnet = 0,
+    Testnet = 1,
+    Regtest = 2,
+    Signet = 3,
+}
+
+impl Network {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Mainnet),
+            1 => Some(Self::Testnet),
+            2 => Some(Self::Regtest),
+            3 => Some(Self::Signet),
+            _ => None,
+        }
+    }
+
+    /// The easiest target a block on this network may have.
+    pub fn pow_limit(self) -> U256 {
+        match self {
+            Self::Mainnet | Self::Testnet => U256::MAX >> 32,
+            Self::Regtest => U256::MAX >> 1,
+            Self::Signet => U256::from(0x0377aeu64) << 216,
+        }
+    }
+
+    /// Whether a block may drop to the minimum difficulty between retargets, as
+    /// testnet allows after 20 minutes without a block.
+    pub fn allows_min_difficulty_blocks(self) -> bool {
+        self == Self::Testnet
+    }
+
+    /// Whether the network keeps a fixed difficulty instead of retargeting.
+    pub fn no_retargeting(self) -> bool {
+        self == Self::Regtest
+    }
+}
@@ -0,0 +1,93 @@
+//! Proof-of-work target arithmetic.
+
+use stylus_sdk::alloy_primitives::U256;
+
+use crate::error::VerifyError;
+
+/// Expands the compact `bits` encoding from a header into a 256-bit target.
+///
+/// Mirrors Bitcoin Core's `arith_uint256::SetCompact`: the top byte is a base-256
+/// exponent and the low 23 bits are the mantissa, shifted right instead of left
+/// when the exponent is below 3. Bit 23 is a sign bit, so a nonzero mantissa with
+/// it set is a negative target, and exponents that push the mantissa past 256
+/// bits overflow. Both are rejected.
+pub fn bits_to_target(bits: u32) -> Result<U256, VerifyError> {
+    let exponent = bits >> 24;
+    let mut mantissa = bits & 0x007f_ffff;
+
+    let target = if exponent <= 3 {
+        mantissa >>= 8 * (3 - exponent);
+        U256::from(mantissa)
+    } else {
+        let overflow = mantissa != 0
+            && (exponent > 34 || (mantissa > 0xff && exponent > 33) || (mantissa > 0xffff && exponent > 32));
+        if overflow {
+            return Err(VerifyError::TargetOverflow);
+        }
+        U256::from(mantissa) << (8 * (exponent - 3) as usize)
+    };
+
+    if mantissa != 0 && bits & 0x0080_0000 != 0 {
+        return Err(VerifyError::NegativeTarget);
+    }
+    Ok(target)
+}
+
+/// Returns true if a block hash (internal byte order) is at or below `target`.
+pub fn hash_meets_target(hash: &[u8; 32], target: U256) -> bool {
+    U256::from_le_bytes(*hash) <= target
+}
+
+/// Compresses a target back into compact `bits`, the inverse of [`bits_to_target`].
+pub fn target_to_bits(target: U256) -> u32 {
+    let mut size = target.bit_len().div_ceil(8) as u32;
+    let mut compact = if size <= 3 {
+        target.to::<u64>() << (8 * (3 - size))
+    } else {
+        (target >> (8 * (size - 3) as usize)).to::<u64>()
+    } as u32;
+
+    // The mantissa is signed; if its top bit would be set, shift it into the exponent.
+    if compact & 0x0080_0000 != 0 {
+        compact >>= 8;
+        size += 1;
+    }
+    compact | (size << 24)
+}
+
+/// Expected amount of work (hashes) to find a block at `target`: `2^256 / (target + 1)`.
+///
+/// Fails with `Overflow` at the two extremes: a zero target, whose work is 2^256,
+/// and `U256::MAX`, where `target + 1` wraps.
+pub fn work(target: U256) -> Result<U256, VerifyError> {
+    // 2^256 doesn't fit in a U256, but (2^256 - target - 1) / (target + 1) + 1 is equal.
+    let divisor = target.checked_add(U256::from(1)).ok_or(VerifyError::Overflow)?;
+    (!target / divisor).checked_add(U256::from(1)).ok_or(VerifyError::Overflow)
+}
+
+/// Computes the `bits` for the first block of a new epoch.
+///
+/// `first_timestamp` and `last_timestamp` belong to the first and last blocks of the
+/// epoch that just ended, and `last_bits` is the last block's difficulty. The
+/// measured timespan is clamped to a factor of four either way, and the result
+/// never exceeds `pow_limit`. Fails with `Overflow` if scaling the old target by the
+/// timespan doesn't fit in 256 bits, which only very easy targets can cause.
+pub fn next_bits(
+    last_bits: u32,
+    first_timestamp: u32,
+    last_timestamp: u32,
+    target_timespan: u32,
+    pow_limit: U256,
+) -> Result<u32, VerifyError> {
+    let actual = (last_timestamp as i64 - first_timestamp as i64)
+        .clamp(target_timespan as i64 / 4, target_timespan as i64 * 4) as u64;
+
+    let mut target = bits_to_target(last_bits)?
+        .checked_mul(U256::from(actual))
+        .ok_or(VerifyError::Overflow)?
+        / U256::from(target_timespan);
+    if target > pow_limit {
+        target = pow_limit;
+    }
+    Ok(target_to_bits(target))
+}
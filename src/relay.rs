@@ -0,0 +1,900 @@
+//! The stateful header relay, built with the `relay` feature (on by default).
+//!
+//! `IRelay` holds every method that touches storage: the checkpoint and owner,
+//! header submission and fork choice, and queries against the stored chain.
+//! Without the feature `BtcVerifier` keeps no state and only the stateless
+//! methods in the crate root are callable.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use stylus_sdk::{
+    alloy_primitives::{Address, FixedBytes, U256, U32, U64, U8},
+    prelude::*,
+};
+
+use crate::header::Header;
+use crate::limits::{MAX_ANCESTOR_DEPTH, MAX_BATCH};
+use crate::params::{Network, MAX_FUTURE_DRIFT, RETARGET_INTERVAL, TARGET_TIMESPAN};
+use crate::{
+    decode_array, decode_display_hash, decode_hex, decode_proof, encode_display_hash, hash, header_fields, merkle,
+    pow, BtcVerifier, ChainState, HeaderFields, OwnershipTransferred, ReorgDetected, VerifyError,
+};
+
+#[public]
+pub trait IRelay {
+    /// Seeds the relay with a trusted checkpoint header.
+    ///
+    /// `anchor_timestamp` is the timestamp of the first block in the checkpoint's
+    /// difficulty epoch. When `height` is itself a retarget boundary it must equal
+    /// the checkpoint's own timestamp. `network` is a `params::Network` discriminant.
+    /// A nonzero `pow_limit` replaces the network's own, for custom signets and
+    /// other chains with their own minimum difficulty. Likewise a nonzero
+    /// `retarget_interval` or `target_timespan` replaces Bitcoin's 2016 blocks and
+    /// two weeks, for forks that retarget on a different schedule.
+    ///
+    /// The caller becomes the owner. Reverts with `AlreadyInitialized` on any later
+    /// call, so the checkpoint and ownership can't be replaced.
+    #[allow(clippy::too_many_arguments)] // One per ABI parameter.
+    fn initialize(
+        &mut self,
+        header_hex: String,
+        height: u32,
+        anchor_timestamp: u32,
+        network: u8,
+        pow_limit: U256,
+        retarget_interval: u32,
+        target_timespan: u32,
+    ) -> Result<(), Vec<u8>>;
+
+    /// Returns the easiest target the relay accepts headers at.
+    fn pow_limit(&self) -> Result<U256, Vec<u8>>;
+
+    /// Returns how many seconds a header's timestamp may be ahead of the current
+    /// Arbitrum block's; two hours unless changed by the owner.
+    fn max_future_drift(&self) -> Result<u32, Vec<u8>>;
+
+    /// Sets how far ahead of the Arbitrum block a header's timestamp may be, in
+    /// seconds. Owner only.
+    ///
+    /// This stands in for Bitcoin's check against network-adjusted time, so it
+    /// assumes the sequencer's clock roughly tracks Bitcoin's; widen it if the
+    /// two drift apart enough to reject honest headers.
+    fn set_max_future_drift(&mut self, seconds: u32) -> Result<(), Vec<u8>>;
+
+    /// Exports the tip and retarget anchor so a redeployed relay can `import_state`
+    /// them instead of re-syncing.
+    fn export_state(&self) -> Result<ChainState, Vec<u8>>;
+
+    /// Seeds the tip, its chainwork and the retarget anchor from another relay's
+    /// `export_state`. Owner only, after `initialize`.
+    ///
+    /// If `tip_header_hex` is given it is stored as the tip and must hash to
+    /// `tip_hash_hex` (`HashMismatch` otherwise); if it is empty the tip must already
+    /// be stored here, e.g. as the checkpoint. The anchor must belong to the tip's
+    /// epoch, as for `initialize`.
+    ///
+    /// The relay must store nothing but its tip, so no older header keeps chainwork
+    /// the import would make meaningless; otherwise it reverts with `ChainNotEmpty`.
+    /// A synced relay has to `prune_headers` up to its tip first, and one that has
+    /// stored stale fork headers, which can't be pruned, has to be redeployed. The
+    /// imported tip replaces the stored one as the lowest height, like a new checkpoint.
+    fn import_state(
+        &mut self,
+        tip_height: u32,
+        tip_hash_hex: String,
+        chainwork_hex: String,
+        anchor_height: u32,
+        anchor_timestamp: u32,
+        tip_header_hex: String,
+    ) -> Result<(), Vec<u8>>;
+
+    /// Returns the relay's owner, or the zero address before `initialize`.
+    fn owner(&self) -> Address;
+
+    /// Returns the account `transfer_ownership` nominated, or the zero address.
+    fn pending_owner(&self) -> Address;
+
+    /// Nominates `new_owner`; ownership only moves once they call `accept_ownership`,
+    /// so a mistyped address can't strand the contract. Owner only.
+    fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>>;
+
+    /// Completes a transfer started by `transfer_ownership`. Pending owner only.
+    fn accept_ownership(&mut self) -> Result<(), Vec<u8>>;
+
+    /// Validates a header that builds on the current tip and makes it the new tip.
+    /// Returns the new tip height.
+    fn submit_header(&mut self, header_hex: String) -> Result<u32, Vec<u8>>;
+
+    /// Validates a header that builds on any stored block, not just the tip, so
+    /// competing branches can be tracked. The branch becomes the best chain once its
+    /// cumulative work exceeds the tip's; on a tie the first-seen tip is kept.
+    /// Returns the header's height.
+    ///
+    /// Every stored block records when its epoch started, so a branch retargets
+    /// from its own epoch even at a boundary the tip has already crossed.
+    ///
+    /// Switching branches moves the tip at once, but one call only re-indexes
+    /// `MAX_ANCESTOR_DEPTH` heights. A deeper reorg is left pending: height lookups
+    /// it hasn't reached, further forks and pruning revert with `ReorgPending` until
+    /// `continue_reorg` finishes it. Extending the new tip still works meanwhile.
+    fn submit_header_fork(&mut self, header_hex: String) -> Result<u32, Vec<u8>>;
+
+    /// Re-indexes up to `MAX_ANCESTOR_DEPTH` more heights of a pending reorg.
+    /// Anyone may call it. Returns true once no reorg is pending, emitting
+    /// `ReorgDetected` on the call that finishes it.
+    fn continue_reorg(&mut self) -> Result<bool, Vec<u8>>;
+
+    /// Returns whether a reorg is waiting on `continue_reorg`.
+    fn reorg_pending(&self) -> bool;
+
+    /// Submits a header on top of the tip, then checks a Merkle proof for `txid_hex`
+    /// against that header's root, so a bridge can relay a block and prove one of its
+    /// transactions in a single call. Returns true, or reverts with `InvalidProof` if
+    /// the proof doesn't match, so the header is only stored along with its proof.
+    fn submit_and_prove(
+        &mut self,
+        header_hex: String,
+        txid_hex: String,
+        proof: Vec<String>,
+        index: u32,
+    ) -> Result<bool, Vec<u8>>;
+
+    /// Submits consecutive headers in one call, returning the new tip height. The
+    /// whole batch reverts if any header is invalid.
+    /// At least one and at most `MAX_BATCH` headers are accepted per call.
+    fn submit_headers(&mut self, headers: Vec<String>) -> Result<u32, Vec<u8>>;
+
+    /// Height of the best tip, however it got there: `submit_headers`, single
+    /// submissions, a reorg or `import_state`. Sync tooling resumes after it.
+    fn sync_cursor(&self) -> u32;
+
+    /// Seconds since a header was last accepted, or since `initialize` if none has
+    /// been, so monitoring can alert when the relay stops advancing.
+    fn seconds_since_last_submit(&self) -> u64;
+
+    /// Returns the height of the current tip. After a deep reorg this is already the
+    /// new tip while lower heights may still be pending; see `submit_header_fork`.
+    fn latest_height(&self) -> Result<u32, Vec<u8>>;
+
+    /// Returns `(lowest_stored_height, tip_height, count_stored)`, a health snapshot
+    /// for dashboards. The count includes headers on stale forks.
+    fn chain_stats(&self) -> Result<(u32, u32, u32), Vec<u8>>;
+
+    /// Deletes the best chain's headers from the lowest stored height up to, but not
+    /// including, `below_height`, which becomes the new lowest height. Owner only.
+    ///
+    /// The tip is never pruned, and at most `MAX_BATCH` heights are cleared per call.
+    /// Stale fork headers aren't indexed by height and stay stored. Returns the
+    /// number of headers deleted. Reverts with `ReorgPending` mid-reorg.
+    fn prune_headers(&mut self, below_height: u32) -> Result<u32, Vec<u8>>;
+
+    /// Returns the raw header accepted at `height`, hex encoded.
+    fn get_header(&self, height: u32) -> Result<String, Vec<u8>>;
+
+    /// Returns the raw 80-byte header accepted at `height`, for contracts that
+    /// would otherwise decode `get_header`'s hex straight back into bytes.
+    fn get_header_bytes(&self, height: u32) -> Result<Vec<u8>, Vec<u8>>;
+
+    /// Returns whether a header is stored at `height`, without loading it. False
+    /// while a pending reorg hasn't re-indexed `height`.
+    fn header_exists(&self, height: u32) -> bool;
+
+    /// Returns the tip header split into the same fields as `parse_header`.
+    fn tip_header_parsed(&self) -> Result<HeaderFields, Vec<u8>>;
+
+    /// Verifies a Merkle proof against the block the relay accepted at `height`.
+    ///
+    /// The block must have at least `min_confirmations` confirmations, counting
+    /// itself, or the call reverts with `InsufficientConfirmations`; this keeps
+    /// callers from acting on a transaction that could still be reorged out.
+    fn verify_tx_at_height(
+        &self,
+        txid_hex: String,
+        height: u32,
+        proof: Vec<String>,
+        index: u32,
+        min_confirmations: u32,
+    ) -> Result<bool, Vec<u8>>;
+
+    /// Verifies several transactions of the block at `height`, reading its header
+    /// once. The i-th result says whether `txids[i]` sits at `indices[i]` given
+    /// `proofs[i]`. Reverts with `LengthMismatch` unless the three arrays line up,
+    /// and with `TooLarge` past `MAX_BATCH` transactions.
+    fn contains_txs(
+        &self,
+        height: u32,
+        txids: Vec<String>,
+        proofs: Vec<Vec<String>>,
+        indices: Vec<u32>,
+    ) -> Result<Vec<bool>, Vec<u8>>;
+
+    /// Re-checks `count` stored headers starting at `from_height` for storage corruption.
+    ///
+    /// Each header must hash to the hash recorded for its height and be stored at that
+    /// height, and each one after the first must link to the one below it. Returns
+    /// false at the first inconsistency. Reverts with `UnknownBlock` if a height in the
+    /// range has no header, and with `TooLarge` if `count` exceeds `MAX_ANCESTOR_DEPTH`.
+    fn audit_chain(&self, from_height: u32, count: u32) -> Result<bool, Vec<u8>>;
+
+    /// Returns whether a header with this hash (display order) has been stored,
+    /// on the best chain or not.
+    fn is_known_block(&self, hash_hex: String) -> Result<bool, Vec<u8>>;
+
+    /// Returns whether the block with this hash (display order) is on the current
+    /// best chain.
+    fn is_canonical_block(&self, hash_hex: String) -> Result<bool, Vec<u8>>;
+
+    /// Returns whether the header's `prev_block` is on the current best chain, so a
+    /// caller can check a header builds on the relay without submitting it. Unlike
+    /// `submit_header` nothing else about the header is validated.
+    fn verify_prev_is_canonical(&self, header_hex: String) -> Result<bool, Vec<u8>>;
+
+    /// Predicts the `bits` of the block after the tip, which must be the last block of
+    /// its epoch, taking `current_timestamp` as the epoch's end. Passing the tip's own
+    /// timestamp gives the consensus value. Reverts with `NotAtRetarget` otherwise.
+    fn predict_next_bits(&self, current_timestamp: u32) -> Result<u32, Vec<u8>>;
+
+    /// Returns the `(height, timestamp)` of the first block in the tip's epoch,
+    /// which the next retarget measures its timespan from.
+    fn retarget_anchor(&self) -> Result<(u32, u32), Vec<u8>>;
+}
+
+#[public]
+impl IRelay for BtcVerifier {
+    fn initialize(
+        &mut self,
+        header_hex: String,
+        height: u32,
+        anchor_timestamp: u32,
+        network: u8,
+        pow_limit: U256,
+        retarget_interval: u32,
+        target_timespan: u32,
+    ) -> Result<(), Vec<u8>> {
+        if self.initialized.get() {
+            return Err(VerifyError::AlreadyInitialized.into());
+        }
+        let network_params = Network::from_u8(network).ok_or(VerifyError::UnknownNetwork)?;
+        let bytes = decode_hex(&header_hex)?;
+        let header = Header::parse(&bytes)?;
+        let retarget_interval = if retarget_interval == 0 { RETARGET_INTERVAL } else { retarget_interval };
+
+        let anchor_height = height - height % retarget_interval;
+        if anchor_height == height && anchor_timestamp != header.timestamp {
+            return Err(VerifyError::InvalidAnchor.into());
+        }
+
+        let hash = FixedBytes::from(hash::hash256(&bytes));
+        self.initialized.set(true);
+        self.retarget_interval.set(U32::from(retarget_interval));
+        self.target_timespan.set(U32::from(if target_timespan == 0 { TARGET_TIMESPAN } else { target_timespan }));
+        self.owner.set(self.vm().msg_sender());
+        self.network.set(U8::from(network));
+        self.pow_limit.set(if pow_limit.is_zero() { network_params.pow_limit() } else { pow_limit });
+        self.store_header(hash, &bytes, height, pow::work(pow::bits_to_target(header.bits)?)?, anchor_timestamp);
+        self.set_tip(hash, height);
+        self.lowest_height.set(U32::from(height));
+        self.max_future_drift.set(U32::from(MAX_FUTURE_DRIFT));
+        self.last_submit_ts.set(U64::from(self.vm().block_timestamp()));
+        Ok(())
+    }
+
+    fn pow_limit(&self) -> Result<U256, Vec<u8>> {
+        self.require_initialized()?;
+        Ok(self.pow_limit.get())
+    }
+
+    fn max_future_drift(&self) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        Ok(self.max_future_drift.get().to())
+    }
+
+    fn set_max_future_drift(&mut self, seconds: u32) -> Result<(), Vec<u8>> {
+        self.require_initialized()?;
+        self.only_owner()?;
+        self.max_future_drift.set(U32::from(seconds));
+        Ok(())
+    }
+
+    fn export_state(&self) -> Result<ChainState, Vec<u8>> {
+        self.require_initialized()?;
+        let tip_hash = self.tip_hash.get();
+        Ok((
+            self.tip_height.get().to(),
+            encode_display_hash(&tip_hash.0),
+            hex::encode(self.chainwork.get(tip_hash).to_be_bytes::<32>()),
+            self.anchor_height.get().to(),
+            self.anchor_timestamp.get().to(),
+        ))
+    }
+
+    fn import_state(
+        &mut self,
+        tip_height: u32,
+        tip_hash_hex: String,
+        chainwork_hex: String,
+        anchor_height: u32,
+        anchor_timestamp: u32,
+        tip_header_hex: String,
+    ) -> Result<(), Vec<u8>> {
+        self.require_initialized()?;
+        self.only_owner()?;
+        let hash = FixedBytes::from(decode_display_hash(&tip_hash_hex)?);
+        let chainwork = U256::from_be_bytes(decode_array::<32>(&chainwork_hex)?);
+
+        let bytes = if tip_header_hex.is_empty() {
+            let stored = self.headers.get(hash).get_bytes();
+            if stored.is_empty() {
+                return Err(VerifyError::UnknownBlock.into());
+            }
+            stored
+        } else {
+            let bytes = decode_hex(&tip_header_hex)?;
+            if hash::hash256(&bytes) != hash.0 {
+                return Err(VerifyError::HashMismatch.into());
+            }
+            bytes
+        };
+        let header = Header::parse(&bytes)?;
+        if anchor_height != tip_height - tip_height % self.retarget_interval()
+            || (anchor_height == tip_height && anchor_timestamp != header.timestamp)
+        {
+            return Err(VerifyError::InvalidAnchor.into());
+        }
+        if self.header_count.get() != U32::from(1) {
+            return Err(VerifyError::ChainNotEmpty.into());
+        }
+
+        // The new tip may be the stored one, e.g. the checkpoint; keep its header.
+        let old_tip: u32 = self.tip_height.get().to();
+        if self.tip_hash.get() == hash {
+            self.canonical_hash_at.delete(old_tip);
+        } else {
+            self.clear_height(old_tip);
+        }
+        self.store_header(hash, &bytes, tip_height, chainwork, anchor_timestamp);
+        self.set_tip(hash, tip_height);
+        self.lowest_height.set(U32::from(tip_height));
+        Ok(())
+    }
+
+    fn owner(&self) -> Address {
+        self.owner.get()
+    }
+
+    fn pending_owner(&self) -> Address {
+        self.pending_owner.get()
+    }
+
+    fn transfer_ownership(&mut self, new_owner: Address) -> Result<(), Vec<u8>> {
+        self.only_owner()?;
+        self.pending_owner.set(new_owner);
+        Ok(())
+    }
+
+    fn accept_ownership(&mut self) -> Result<(), Vec<u8>> {
+        let caller = self.vm().msg_sender();
+        if caller == Address::ZERO || caller != self.pending_owner.get() {
+            return Err(VerifyError::NotPendingOwner.into());
+        }
+        let previous_owner = self.owner.get();
+        self.owner.set(caller);
+        self.pending_owner.set(Address::ZERO);
+        self.vm().log(OwnershipTransferred { previousOwner: previous_owner, newOwner: caller });
+        Ok(())
+    }
+
+    fn submit_header(&mut self, header_hex: String) -> Result<u32, Vec<u8>> {
+        let bytes = decode_hex(&header_hex)?;
+        Ok(self.extend_tip(&bytes)?)
+    }
+
+    fn submit_header_fork(&mut self, header_hex: String) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        self.require_no_reorg()?;
+        let bytes = decode_hex(&header_hex)?;
+        let header = Header::parse(&bytes)?;
+        let parent_hash = FixedBytes::from(header.prev_block);
+        if self.headers.get(parent_hash).is_empty() {
+            return Err(VerifyError::UnknownBlock.into());
+        }
+        let height = self.heights.get(parent_hash).to::<u32>() + 1;
+
+        let (hash, chainwork) = self.accept_header(&bytes, &header, parent_hash, height)?;
+        let old_tip = self.tip_hash.get();
+        if chainwork > self.chainwork.get(old_tip) {
+            let old_height = self.tip_height.get().to::<u32>();
+            self.set_tip(hash, height);
+            if parent_hash != old_tip {
+                self.reorg_cursor.set(parent_hash);
+                self.reorg_old_tip.set(old_tip);
+                self.reorg_stale_height.set(U32::from(old_height));
+                self.advance_reorg()?;
+            }
+        }
+        Ok(height)
+    }
+
+    fn continue_reorg(&mut self) -> Result<bool, Vec<u8>> {
+        if !self.reorg_pending() {
+            return Ok(true);
+        }
+        Ok(self.advance_reorg()?)
+    }
+
+    fn reorg_pending(&self) -> bool {
+        self.reorg_cursor.get() != FixedBytes::ZERO
+    }
+
+    fn submit_and_prove(
+        &mut self,
+        header_hex: String,
+        txid_hex: String,
+        proof: Vec<String>,
+        index: u32,
+    ) -> Result<bool, Vec<u8>> {
+        let proof = decode_proof(&proof)?;
+        let txid = decode_display_hash(&txid_hex)?;
+        let bytes = decode_hex(&header_hex)?;
+        let header = Header::parse(&bytes)?;
+        if merkle::root_from_proof(&txid, &proof, index) != Some(header.merkle_root) {
+            return Err(VerifyError::InvalidProof.into());
+        }
+        self.extend_tip(&bytes)?;
+        Ok(true)
+    }
+
+    fn submit_headers(&mut self, headers: Vec<String>) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        if headers.is_empty() {
+            return Err(VerifyError::EmptyInput.into());
+        }
+        if headers.len() > MAX_BATCH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        let mut height = self.tip_height.get().to();
+        for header_hex in &headers {
+            height = self.extend_tip(&decode_hex(header_hex)?)?;
+        }
+        Ok(height)
+    }
+
+    fn sync_cursor(&self) -> u32 {
+        self.sync_cursor.get().to()
+    }
+
+    fn seconds_since_last_submit(&self) -> u64 {
+        self.vm().block_timestamp().saturating_sub(self.last_submit_ts.get().to())
+    }
+
+    fn latest_height(&self) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        Ok(self.tip_height.get().to())
+    }
+
+    fn chain_stats(&self) -> Result<(u32, u32, u32), Vec<u8>> {
+        self.require_initialized()?;
+        Ok((self.lowest_height.get().to(), self.tip_height.get().to(), self.header_count.get().to()))
+    }
+
+    fn prune_headers(&mut self, below_height: u32) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        self.only_owner()?;
+        self.require_no_reorg()?;
+        let lowest = self.lowest_height.get().to::<u32>();
+        if below_height <= lowest {
+            return Ok(0);
+        }
+        if below_height > self.tip_height.get().to::<u32>() {
+            return Err(VerifyError::InvalidPruneHeight.into());
+        }
+        if below_height - lowest > MAX_BATCH as u32 {
+            return Err(VerifyError::TooLarge.into());
+        }
+
+        let mut pruned = 0u32;
+        for height in lowest..below_height {
+            if self.clear_height(height) {
+                pruned += 1;
+            }
+        }
+        self.lowest_height.set(U32::from(below_height));
+        Ok(pruned)
+    }
+
+    fn get_header(&self, height: u32) -> Result<String, Vec<u8>> {
+        Ok(hex::encode(self.get_header_bytes(height)?))
+    }
+
+    fn get_header_bytes(&self, height: u32) -> Result<Vec<u8>, Vec<u8>> {
+        self.require_indexed(height)?;
+        let hash = self.canonical_hash_at.get(height);
+        if hash == FixedBytes::ZERO {
+            return Err(VerifyError::UnknownBlock.into());
+        }
+        Ok(self.headers.get(hash).get_bytes())
+    }
+
+    fn header_exists(&self, height: u32) -> bool {
+        self.require_indexed(height).is_ok() && self.canonical_hash_at.get(height) != FixedBytes::ZERO
+    }
+
+    fn tip_header_parsed(&self) -> Result<HeaderFields, Vec<u8>> {
+        self.require_initialized()?;
+        let header = Header::parse(&self.headers.get(self.tip_hash.get()).get_bytes())?;
+        Ok(header_fields(&header))
+    }
+
+    fn verify_tx_at_height(
+        &self,
+        txid_hex: String,
+        height: u32,
+        proof: Vec<String>,
+        index: u32,
+        min_confirmations: u32,
+    ) -> Result<bool, Vec<u8>> {
+        self.require_initialized()?;
+        let proof = decode_proof(&proof)?;
+        self.require_indexed(height)?;
+        let tip_height: u32 = self.tip_height.get().to();
+        let hash = self.canonical_hash_at.get(height);
+        if height > tip_height || hash == FixedBytes::ZERO {
+            return Err(VerifyError::UnknownBlock.into());
+        }
+        if tip_height - height + 1 < min_confirmations {
+            return Err(VerifyError::InsufficientConfirmations.into());
+        }
+
+        let header = Header::parse(&self.headers.get(hash).get_bytes())?;
+        let txid = decode_display_hash(&txid_hex)?;
+        Ok(merkle::root_from_proof(&txid, &proof, index) == Some(header.merkle_root))
+    }
+
+    fn contains_txs(
+        &self,
+        height: u32,
+        txids: Vec<String>,
+        proofs: Vec<Vec<String>>,
+        indices: Vec<u32>,
+    ) -> Result<Vec<bool>, Vec<u8>> {
+        self.require_initialized()?;
+        if txids.len() != proofs.len() || txids.len() != indices.len() {
+            return Err(VerifyError::LengthMismatch.into());
+        }
+        if txids.len() > MAX_BATCH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        self.require_indexed(height)?;
+        let hash = self.canonical_hash_at.get(height);
+        if height > self.tip_height.get().to::<u32>() || hash == FixedBytes::ZERO {
+            return Err(VerifyError::UnknownBlock.into());
+        }
+
+        let root = Header::parse(&self.headers.get(hash).get_bytes())?.merkle_root;
+        let mut results = Vec::with_capacity(txids.len());
+        for ((txid_hex, proof), &index) in txids.iter().zip(&proofs).zip(&indices) {
+            let txid = decode_display_hash(txid_hex)?;
+            results.push(merkle::root_from_proof(&txid, &decode_proof(proof)?, index) == Some(root));
+        }
+        Ok(results)
+    }
+
+    fn audit_chain(&self, from_height: u32, count: u32) -> Result<bool, Vec<u8>> {
+        if count > MAX_ANCESTOR_DEPTH {
+            return Err(VerifyError::TooLarge.into());
+        }
+        let mut prev_hash: Option<FixedBytes<32>> = None;
+        for height in from_height..from_height.saturating_add(count) {
+            self.require_indexed(height)?;
+            let hash = self.canonical_hash_at.get(height);
+            if hash == FixedBytes::ZERO {
+                return Err(VerifyError::UnknownBlock.into());
+            }
+            let bytes = self.headers.get(hash).get_bytes();
+            let Ok(header) = Header::parse(&bytes) else {
+                return Ok(false);
+            };
+            if hash::hash256(&bytes) != hash.0 || self.heights.get(hash).to::<u32>() != height {
+                return Ok(false);
+            }
+            if prev_hash.is_some_and(|prev| prev.0 != header.prev_block) {
+                return Ok(false);
+            }
+            prev_hash = Some(hash);
+        }
+        Ok(true)
+    }
+
+    fn is_known_block(&self, hash_hex: String) -> Result<bool, Vec<u8>> {
+        let hash = FixedBytes::from(decode_display_hash(&hash_hex)?);
+        Ok(!self.headers.get(hash).is_empty())
+    }
+
+    fn is_canonical_block(&self, hash_hex: String) -> Result<bool, Vec<u8>> {
+        let hash = FixedBytes::from(decode_display_hash(&hash_hex)?);
+        Ok(self.is_canonical(hash)?)
+    }
+
+    fn verify_prev_is_canonical(&self, header_hex: String) -> Result<bool, Vec<u8>> {
+        let header = Header::parse(&decode_hex(&header_hex)?)?;
+        Ok(self.is_canonical(FixedBytes::from(header.prev_block))?)
+    }
+
+    fn predict_next_bits(&self, current_timestamp: u32) -> Result<u32, Vec<u8>> {
+        self.require_initialized()?;
+        if (self.tip_height.get().to::<u32>() + 1) % self.retarget_interval() != 0 {
+            return Err(VerifyError::NotAtRetarget.into());
+        }
+        let tip = Header::parse(&self.headers.get(self.tip_hash.get()).get_bytes())?;
+        let network = self.network();
+        if network.no_retargeting() {
+            return Ok(tip.bits);
+        }
+        Ok(pow::next_bits(
+            tip.bits,
+            self.anchor_timestamp.get().to(),
+            current_timestamp,
+            self.target_timespan.get().to(),
+            self.pow_limit.get(),
+        )?)
+    }
+
+    fn retarget_anchor(&self) -> Result<(u32, u32), Vec<u8>> {
+        self.require_initialized()?;
+        Ok((self.anchor_height.get().to(), self.anchor_timestamp.get().to()))
+    }
+}
+
+// Outside the #[public] impls, so never part of the contract's ABI.
+#[cfg(any(test, feature = "test-utils"))]
+impl BtcVerifier {
+    /// Points the tip at `hash_hex` (display order) at `height` with the given
+    /// chainwork (32-byte big-endian hex), skipping every check, so tests can stand
+    /// up deep chains without mining them. No header is stored for the new tip.
+    pub fn test_set_tip(&mut self, height: u32, hash_hex: String, chainwork_hex: String) -> Result<(), Vec<u8>> {
+        let hash = FixedBytes::from(decode_display_hash(&hash_hex)?);
+        self.chainwork.insert(hash, U256::from_be_bytes(decode_array::<32>(&chainwork_hex)?));
+        self.heights.insert(hash, U32::from(height));
+        self.canonical_hash_at.insert(height, hash);
+        self.tip_hash.set(hash);
+        self.tip_height.set(U32::from(height));
+        Ok(())
+    }
+}
+
+impl BtcVerifier {
+    /// Whether `hash` (internal order) is stored at its height on the best chain.
+    fn is_canonical(&self, hash: FixedBytes<32>) -> Result<bool, VerifyError> {
+        if self.headers.get(hash).is_empty() {
+            return Ok(false);
+        }
+        let height = self.heights.get(hash).to::<u32>();
+        self.require_indexed(height)?;
+        Ok(self.canonical_hash_at.get(height) == hash)
+    }
+
+    fn require_no_reorg(&self) -> Result<(), VerifyError> {
+        if self.reorg_pending() {
+            return Err(VerifyError::ReorgPending);
+        }
+        Ok(())
+    }
+
+    /// Fails with `ReorgPending` if a pending reorg may still map `height` to the
+    /// old chain: at or below its cursor, or above the tip.
+    fn require_indexed(&self, height: u32) -> Result<(), VerifyError> {
+        let cursor = self.reorg_cursor.get();
+        if cursor != FixedBytes::ZERO
+            && (height <= self.heights.get(cursor).to::<u32>() || height > self.tip_height.get().to::<u32>())
+        {
+            return Err(VerifyError::ReorgPending);
+        }
+        Ok(())
+    }
+
+    fn require_initialized(&self) -> Result<(), VerifyError> {
+        if !self.initialized.get() {
+            return Err(VerifyError::NotInitialized);
+        }
+        Ok(())
+    }
+
+    fn only_owner(&self) -> Result<(), VerifyError> {
+        if self.vm().msg_sender() != self.owner.get() {
+            return Err(VerifyError::NotOwner);
+        }
+        Ok(())
+    }
+
+    /// Blocks per difficulty epoch, as set at `initialize`.
+    pub(crate) fn retarget_interval(&self) -> u32 {
+        // Zero only before `initialize`, when nothing retargets anyway.
+        match self.retarget_interval.get().to::<u32>() {
+            0 => RETARGET_INTERVAL,
+            interval => interval,
+        }
+    }
+
+    fn network(&self) -> Network {
+        // `initialize` only ever stores valid discriminants.
+        Network::from_u8(self.network.get().to()).unwrap_or(Network::Mainnet)
+    }
+
+    /// Validates `bytes` as the child of the current tip and advances the tip to it.
+    fn extend_tip(&mut self, bytes: &[u8]) -> Result<u32, VerifyError> {
+        self.require_initialized()?;
+        let header = Header::parse(bytes)?;
+        let parent_hash = self.tip_hash.get();
+        if header.prev_block != parent_hash.0 {
+            return Err(VerifyError::PrevHashMismatch);
+        }
+        let height = self.tip_height.get().to::<u32>() + 1;
+
+        let (hash, _) = self.accept_header(bytes, &header, parent_hash, height)?;
+        self.set_tip(hash, height);
+        Ok(height)
+    }
+
+    /// Checks difficulty and proof of work for `header`, the child of the stored
+    /// block `parent_hash`, then stores it. Returns its hash and cumulative chainwork.
+    /// A header that is already stored is rejected, so it can't be counted twice.
+    fn accept_header(
+        &mut self,
+        bytes: &[u8],
+        header: &Header,
+        parent_hash: FixedBytes<32>,
+        height: u32,
+    ) -> Result<(FixedBytes<32>, U256), VerifyError> {
+        let hash = hash::hash256(bytes);
+        if !self.headers.get(FixedBytes::from(hash)).is_empty() {
+            return Err(VerifyError::DuplicateHeader);
+        }
+        let parent = Header::parse(&self.headers.get(parent_hash).get_bytes())?;
+        let max_future_drift: u32 = self.max_future_drift.get().to();
+        if header.timestamp as u64 > self.vm().block_timestamp() + max_future_drift as u64 {
+            return Err(VerifyError::TimestampTooNew);
+        }
+        self.check_difficulty(header, &parent, parent_hash, height)?;
+        let network = self.network();
+        let work = if network == Network::Regtest {
+            // Regtest blocks are mined at minimum difficulty with arbitrary timestamps,
+            // so only linkage is enforced and every block counts as pow_limit work.
+            pow::work(self.pow_limit.get())?
+        } else {
+            let target = pow::bits_to_target(header.bits)?;
+            if target > self.pow_limit.get() || !pow::hash_meets_target(&hash, target) {
+                return Err(VerifyError::InsufficientPow);
+            }
+            pow::work(target)?
+        };
+
+        let hash = FixedBytes::from(hash);
+        let chainwork = self.chainwork.get(parent_hash).checked_add(work).ok_or(VerifyError::Overflow)?;
+        let epoch_start = if height % self.retarget_interval() == 0 {
+            header.timestamp
+        } else {
+            self.epoch_start.get(parent_hash).to()
+        };
+        self.store_header(hash, bytes, height, chainwork, epoch_start);
+        self.last_submit_ts.set(U64::from(self.vm().block_timestamp()));
+        Ok((hash, chainwork))
+    }
+
+    /// Rewrites up to `MAX_ANCESTOR_DEPTH` heights of the pending reorg: first the
+    /// old chain's heights above the tip are cleared, then `canonical_hash_at` is
+    /// pointed along the new branch from `reorg_cursor` down to where it meets the
+    /// old chain. Emits `ReorgDetected` and returns true once that's done.
+    fn advance_reorg(&mut self) -> Result<bool, VerifyError> {
+        let tip_height: u32 = self.tip_height.get().to();
+        let mut stale: u32 = self.reorg_stale_height.get().to();
+        let mut hash = self.reorg_cursor.get();
+        for _ in 0..MAX_ANCESTOR_DEPTH {
+            if stale > tip_height {
+                self.canonical_hash_at.delete(stale);
+                stale -= 1;
+                continue;
+            }
+            let height = self.heights.get(hash).to::<u32>();
+            if self.canonical_hash_at.get(height) == hash {
+                break;
+            }
+            self.canonical_hash_at.insert(height, hash);
+            hash = FixedBytes::from(Header::parse(&self.headers.get(hash).get_bytes())?.prev_block);
+        }
+        self.reorg_stale_height.set(U32::from(stale));
+        let fork_height = self.heights.get(hash).to::<u32>();
+        if stale > tip_height || self.canonical_hash_at.get(fork_height) != hash {
+            self.reorg_cursor.set(hash);
+            return Ok(false);
+        }
+
+        self.reorg_cursor.set(FixedBytes::ZERO);
+        let old_tip = self.reorg_old_tip.get();
+        let old_height = self.heights.get(old_tip).to::<u32>();
+        self.vm().log(ReorgDetected {
+            oldHeight: old_height,
+            oldTip: old_tip,
+            newHeight: tip_height,
+            newTip: self.tip_hash.get(),
+            depth: U256::from(old_height - fork_height),
+        });
+        Ok(true)
+    }
+
+    /// Checks `header.bits` against the difficulty required at `height`, where
+    /// `parent` is the stored block `parent_hash`.
+    fn check_difficulty(
+        &self,
+        header: &Header,
+        parent: &Header,
+        parent_hash: FixedBytes<32>,
+        height: u32,
+    ) -> Result<(), VerifyError> {
+        let interval = self.retarget_interval();
+        if height % interval != 0 {
+            // Mid-epoch the difficulty carries over from the parent. Testnet's
+            // minimum-difficulty blocks break that, so it isn't checked there.
+            if header.bits != parent.bits && !self.network().allows_min_difficulty_blocks() {
+                return Err(VerifyError::BadDifficulty);
+            }
+            return Ok(());
+        }
+        // The epoch being closed is the parent's, whichever branch it is on.
+        let epoch_start: u32 = self.epoch_start.get(parent_hash).to();
+        if epoch_start == 0 {
+            return Err(VerifyError::AnchorUnavailable);
+        }
+        let network = self.network();
+        let expected = if network.no_retargeting() {
+            parent.bits
+        } else {
+            pow::next_bits(
+                parent.bits,
+                epoch_start,
+                parent.timestamp,
+                self.target_timespan.get().to(),
+                self.pow_limit.get(),
+            )?
+        };
+        if header.bits != expected {
+            return Err(VerifyError::BadDifficulty);
+        }
+        Ok(())
+    }
+
+    /// Deletes the best chain's header at `height`, if there is one, and reports
+    /// whether there was.
+    fn clear_height(&mut self, height: u32) -> bool {
+        let hash = self.canonical_hash_at.get(height);
+        if hash == FixedBytes::ZERO {
+            return false;
+        }
+        self.headers.delete(hash);
+        self.chainwork.delete(hash);
+        self.heights.delete(hash);
+        self.epoch_start.delete(hash);
+        self.canonical_hash_at.delete(height);
+        self.header_count.set(self.header_count.get() - U32::from(1));
+        true
+    }
+
+    fn store_header(&mut self, hash: FixedBytes<32>, bytes: &[u8], height: u32, chainwork: U256, epoch_start: u32) {
+        if self.headers.get(hash).is_empty() {
+            self.header_count.set(self.header_count.get() + U32::from(1));
+        }
+        self.headers.setter(hash).set_bytes(bytes);
+        self.chainwork.insert(hash, chainwork);
+        self.heights.insert(hash, U32::from(height));
+        self.epoch_start.insert(hash, U32::from(epoch_start));
+    }
+
+    /// Makes the stored block `hash` the best tip, with the anchor at the start of its epoch.
+    fn set_tip(&mut self, hash: FixedBytes<32>, height: u32) {
+        self.tip_hash.set(hash);
+        self.tip_height.set(U32::from(height));
+        self.canonical_hash_at.insert(height, hash);
+        self.sync_cursor.set(U32::from(height));
+        self.anchor_height.set(U32::from(height - height % self.retarget_interval()));
+        self.anchor_timestamp.set(self.epoch_start.get(hash));
+    }
+}
@@ -0,0 +1,95 @@
+//! Just enough Bitcoin Script to split scripts into opcodes and pushes, and to
+//! build the P2PKH template.
+
+use alloc::vec::Vec;
+
+use crate::error::VerifyError;
+
+pub const OP_0: u8 = 0x00;
+pub const OP_PUSHDATA1: u8 = 0x4c;
+pub const OP_PUSHDATA2: u8 = 0x4d;
+pub const OP_PUSHDATA4: u8 = 0x4e;
+pub const OP_1: u8 = 0x51;
+pub const OP_16: u8 = 0x60;
+pub const OP_RETURN: u8 = 0x6a;
+pub const OP_DUP: u8 = 0x76;
+pub const OP_EQUAL: u8 = 0x87;
+pub const OP_EQUALVERIFY: u8 = 0x88;
+pub const OP_HASH160: u8 = 0xa9;
+pub const OP_CODESEPARATOR: u8 = 0xab;
+pub const OP_CHECKSIG: u8 = 0xac;
+pub const OP_CHECKMULTISIG: u8 = 0xae;
+
+/// One element of a script.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction<'a> {
+    /// Data pushed by `OP_0` or any of the push opcodes.
+    Push(&'a [u8]),
+    /// Any other opcode.
+    Op(u8),
+}
+
+/// Iterates over the instructions of a script, failing with `MalformedTx` if a
+/// push runs past the end.
+pub struct Instructions<'a> {
+    script: &'a [u8],
+    pos: usize,
+}
+
+pub fn instructions(script: &[u8]) -> Instructions<'_> {
+    Instructions { script, pos: 0 }
+}
+
+impl<'a> Instructions<'a> {
+    /// Offset of the next instruction within the script.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], VerifyError> {
+        let Some(data) = self.script.get(self.pos..self.pos.saturating_add(len)) else {
+            self.pos = self.script.len();
+            return Err(VerifyError::MalformedTx);
+        };
+        self.pos += len;
+        Ok(data)
+    }
+
+    fn take_len(&mut self, width: usize) -> Result<usize, VerifyError> {
+        let mut len = [0u8; 4];
+        len[..width].copy_from_slice(self.take(width)?);
+        Ok(u32::from_le_bytes(len) as usize)
+    }
+}
+
+impl<'a> Iterator for Instructions<'a> {
+    type Item = Result<Instruction<'a>, VerifyError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let opcode = *self.script.get(self.pos)?;
+        self.pos += 1;
+        let len = match opcode {
+            0x01..=0x4b => Ok(opcode as usize),
+            OP_PUSHDATA1 => self.take_len(1),
+            OP_PUSHDATA2 => self.take_len(2),
+            OP_PUSHDATA4 => self.take_len(4),
+            OP_0 => return Some(Ok(Instruction::Push(&[]))),
+            _ => return Some(Ok(Instruction::Op(opcode))),
+        };
+        Some(len.and_then(|len| self.take(len)).map(Instruction::Push))
+    }
+}
+
+/// Returns the number encoded by a small-integer opcode `OP_1`..`OP_16`.
+pub fn small_int(opcode: u8) -> Option<u8> {
+    (OP_1..=OP_16).contains(&opcode).then(|| opcode - OP_1 + 1)
+}
+
+/// The P2PKH script `OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG`.
+pub fn p2pkh(pubkey_hash: &[u8; 20]) -> Vec<u8> {
+    let mut script = Vec::with_capacity(25);
+    script.extend_from_slice(&[OP_DUP, OP_HASH160, 20]);
+    script.extend_from_slice(pubkey_hash);
+    script.extend_from_slice(&[OP_EQUALVERIFY, OP_CHECKSIG]);
+    script
+}
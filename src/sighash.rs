@@ -0,0 +1,141 @@
+//! Signature hashes that ECDSA signatures in transaction inputs commit to.
+
+use alloc::vec::Vec;
+
+use crate::error::VerifyError;
+use crate::hash::hash256;
+use crate::script::{instructions, Instruction, OP_CODESEPARATOR};
+use crate::tx::{write_var_bytes, Transaction, TxOut};
+
+pub const SIGHASH_ALL: u32 = 1;
+pub const SIGHASH_NONE: u32 = 2;
+pub const SIGHASH_SINGLE: u32 = 3;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+/// Rejects sighash types other than `SIGHASH_ALL`, `SIGHASH_NONE` and
+/// `SIGHASH_SINGLE`, each optionally with `SIGHASH_ANYONECANPAY`.
+pub fn check_type(sighash_type: u32) -> Result<(), VerifyError> {
+    match sighash_type & !SIGHASH_ANYONECANPAY {
+        SIGHASH_ALL | SIGHASH_NONE | SIGHASH_SINGLE => Ok(()),
+        _ => Err(VerifyError::BadSighashType),
+    }
+}
+
+/// The legacy (pre-segwit) signature hash of `tx`'s input `input_index` spending
+/// an output locked by `script_code`, following Core's `SignatureHash`.
+///
+/// `OP_CODESEPARATOR`s are stripped from `script_code`; the caller removes the
+/// signature itself if it appears there. As in Core, an `input_index` past the
+/// inputs, or `SIGHASH_SINGLE` without a matching output, hashes to the value one.
+pub fn legacy(tx: &Transaction, input_index: usize, script_code: &[u8], sighash_type: u32) -> Result<[u8; 32], VerifyError> {
+    let base_type = sighash_type & 0x1f;
+    if input_index >= tx.inputs.len() || (base_type == SIGHASH_SINGLE && input_index >= tx.outputs.len()) {
+        let mut one = [0u8; 32];
+        one[0] = 1;
+        return Ok(one);
+    }
+
+    let script_code = strip_codeseparators(script_code)?;
+    let mut copy = tx.clone();
+    for (i, input) in copy.inputs.iter_mut().enumerate() {
+        if i == input_index {
+            input.script_sig = script_code.clone();
+        } else {
+            input.script_sig.clear();
+            if base_type == SIGHASH_NONE || base_type == SIGHASH_SINGLE {
+                input.sequence = 0;
+            }
+        }
+    }
+    if sighash_type & SIGHASH_ANYONECANPAY != 0 {
+        copy.inputs = copy.inputs.split_off(input_index);
+        copy.inputs.truncate(1);
+    }
+    match base_type {
+        SIGHASH_NONE => copy.outputs.clear(),
+        SIGHASH_SINGLE => {
+            copy.outputs.truncate(input_index + 1);
+            for output in &mut copy.outputs[..input_index] {
+                *output = TxOut { value: u64::MAX, script_pubkey: Vec::new() };
+            }
+        }
+        _ => {}
+    }
+
+    let mut preimage = copy.serialize_without_witness();
+    preimage.extend_from_slice(&sighash_type.to_le_bytes());
+    Ok(hash256(&preimage))
+}
+
+/// The BIP143 signature hash of `tx`'s segwit v0 input `input_index`, which spends
+/// `amount` satoshis locked by `script_code`. Fails with `InputOutOfRange` if there
+/// is no such input. `SIGHASH_SINGLE` without a matching output commits to no
+/// outputs, as BIP143 fixed the legacy bug.
+pub fn segwit_v0(
+    tx: &Transaction,
+    input_index: usize,
+    script_code: &[u8],
+    amount: u64,
+    sighash_type: u32,
+) -> Result<[u8; 32], VerifyError> {
+    let input = tx.inputs.get(input_index).ok_or(VerifyError::InputOutOfRange)?;
+    let base_type = sighash_type & 0x1f;
+    let anyone_can_pay = sighash_type & SIGHASH_ANYONECANPAY != 0;
+
+    let mut hash_prevouts = [0u8; 32];
+    if !anyone_can_pay {
+        let mut prevouts = Vec::with_capacity(tx.inputs.len() * 36);
+        for input in &tx.inputs {
+            prevouts.extend_from_slice(&input.prev_txid);
+            prevouts.extend_from_slice(&input.prev_vout.to_le_bytes());
+        }
+        hash_prevouts = hash256(&prevouts);
+    }
+    let mut hash_sequence = [0u8; 32];
+    if !anyone_can_pay && base_type != SIGHASH_SINGLE && base_type != SIGHASH_NONE {
+        let sequences: Vec<u8> = tx.inputs.iter().flat_map(|input| input.sequence.to_le_bytes()).collect();
+        hash_sequence = hash256(&sequences);
+    }
+    let outputs = match base_type {
+        SIGHASH_NONE => &[][..],
+        SIGHASH_SINGLE => tx.outputs.get(input_index..=input_index).unwrap_or(&[]),
+        _ => &tx.outputs[..],
+    };
+    let mut hash_outputs = [0u8; 32];
+    if !outputs.is_empty() {
+        let mut serialized = Vec::new();
+        for output in outputs {
+            serialized.extend_from_slice(&output.value.to_le_bytes());
+            write_var_bytes(&mut serialized, &output.script_pubkey);
+        }
+        hash_outputs = hash256(&serialized);
+    }
+
+    let mut preimage = Vec::with_capacity(160 + script_code.len());
+    preimage.extend_from_slice(&tx.version.to_le_bytes());
+    preimage.extend_from_slice(&hash_prevouts);
+    preimage.extend_from_slice(&hash_sequence);
+    preimage.extend_from_slice(&input.prev_txid);
+    preimage.extend_from_slice(&input.prev_vout.to_le_bytes());
+    write_var_bytes(&mut preimage, script_code);
+    preimage.extend_from_slice(&amount.to_le_bytes());
+    preimage.extend_from_slice(&input.sequence.to_le_bytes());
+    preimage.extend_from_slice(&hash_outputs);
+    preimage.extend_from_slice(&tx.lock_time.to_le_bytes());
+    preimage.extend_from_slice(&sighash_type.to_le_bytes());
+    Ok(hash256(&preimage))
+}
+
+fn strip_codeseparators(script: &[u8]) -> Result<Vec<u8>, VerifyError> {
+    // Only real opcodes are dropped, not 0xab bytes inside pushes.
+    let mut stripped = Vec::with_capacity(script.len());
+    let mut iter = instructions(script);
+    let mut start = 0;
+    while let Some(instruction) = iter.next() {
+        if instruction? != Instruction::Op(OP_CODESEPARATOR) {
+            stripped.extend_from_slice(&script[start..iter.offset()]);
+        }
+        start = iter.offset();
+    }
+    Ok(stripped)
+}
@@ -0,0 +1,127 @@
+//! BIP325 signet block signatures.
+//!
+//! A signet block is valid only if its coinbase carries a solution satisfying the
+//! network's challenge script. The solution signs a virtual `to_sign` transaction
+//! that spends a `to_spend` output locked by the challenge, committing to the
+//! header's version, previous block, signet Merkle root and time.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::ecc;
+use crate::error::VerifyError;
+use crate::header::Header;
+use crate::script::{instructions, small_int, Instruction, OP_0, OP_1, OP_CHECKMULTISIG, OP_RETURN};
+use crate::sighash;
+use crate::tx::{Reader, Transaction, TxIn, TxOut};
+
+/// Checks a signet block solution: the scriptSig and witness stack a signer put in
+/// the coinbase, serialized as in BIP325. `header.merkle_root` must be the signet
+/// Merkle root, computed with the solution removed from the coinbase.
+///
+/// Only the challenge templates signets use in practice are evaluated: bare
+/// `m`-of-`n` `OP_CHECKMULTISIG`, as on the default signet, and the trivial `OP_TRUE`.
+/// Any other challenge fails with `UnsupportedScript`.
+pub fn verify_solution(header: &Header, challenge: &[u8], solution: &[u8]) -> Result<bool, VerifyError> {
+    let mut reader = Reader::new(solution);
+    let script_sig = reader.read_var_bytes()?;
+    let witness_items = reader.read_compact_size()?;
+    let mut witness = Vec::new();
+    for _ in 0..witness_items {
+        witness.push(reader.read_var_bytes()?);
+    }
+    if !reader.is_empty() {
+        return Err(VerifyError::MalformedTx);
+    }
+
+    if challenge == [OP_1] {
+        return Ok(true);
+    }
+    let Some(Multisig { required, keys }) = parse_multisig(challenge)? else {
+        return Err(VerifyError::UnsupportedScript);
+    };
+    // A bare challenge must not be given witness data.
+    if !witness.is_empty() {
+        return Ok(false);
+    }
+
+    // NULLDUMMY: the extra item CHECKMULTISIG pops must be empty.
+    let pushes = instructions(&script_sig)
+        .map(|instruction| match instruction? {
+            Instruction::Push(data) => Ok(Some(data)),
+            Instruction::Op(_) => Ok(None),
+        })
+        .collect::<Result<Option<Vec<_>>, VerifyError>>()?;
+    let Some([dummy, sigs @ ..]) = pushes.as_deref() else {
+        return Ok(false);
+    };
+    if !dummy.is_empty() || sigs.len() != required {
+        return Ok(false);
+    }
+
+    let to_sign = to_sign(header, challenge);
+    // Signatures must match the keys in order, each key used at most once.
+    let mut keys = keys.iter();
+    for sig in sigs {
+        let Some((&sighash_type, der)) = sig.split_last() else {
+            return Ok(false);
+        };
+        let digest = sighash::legacy(&to_sign, 0, challenge, sighash_type as u32)?;
+        if !keys.any(|key| ecc::verify_ecdsa(&digest, der, key).unwrap_or(false)) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// A bare `OP_m <key>... OP_n OP_CHECKMULTISIG` script.
+struct Multisig<'a> {
+    required: usize,
+    keys: Vec<&'a [u8]>,
+}
+
+/// Recognizes a bare multisig script, returning `None` for anything else.
+fn parse_multisig(script: &[u8]) -> Result<Option<Multisig<'_>>, VerifyError> {
+    let ops = instructions(script).collect::<Result<Vec<_>, VerifyError>>()?;
+    let [Instruction::Op(m), keys @ .., Instruction::Op(n), Instruction::Op(OP_CHECKMULTISIG)] = ops.as_slice() else {
+        return Ok(None);
+    };
+    let keys = keys
+        .iter()
+        .map(|op| match op {
+            Instruction::Push(key) => Some(*key),
+            Instruction::Op(_) => None,
+        })
+        .collect::<Option<Vec<_>>>();
+    match (small_int(*m), small_int(*n), keys) {
+        (Some(m), Some(n), Some(keys)) if n as usize == keys.len() && m <= n => {
+            Ok(Some(Multisig { required: m as usize, keys }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Builds BIP325's `to_sign` transaction for `header` under `challenge`.
+fn to_sign(header: &Header, challenge: &[u8]) -> Transaction {
+    let mut block_data = Vec::with_capacity(72);
+    block_data.extend_from_slice(&header.version.to_le_bytes());
+    block_data.extend_from_slice(&header.prev_block);
+    block_data.extend_from_slice(&header.merkle_root);
+    block_data.extend_from_slice(&header.timestamp.to_le_bytes());
+
+    // scriptSig: OP_0 followed by a direct push of the 72 bytes of block data.
+    let mut script_sig = vec![OP_0, block_data.len() as u8];
+    script_sig.extend_from_slice(&block_data);
+    let to_spend = Transaction {
+        version: 0,
+        inputs: vec![TxIn { prev_txid: [0u8; 32], prev_vout: u32::MAX, script_sig, sequence: 0, witness: Vec::new() }],
+        outputs: vec![TxOut { value: 0, script_pubkey: challenge.to_vec() }],
+        lock_time: 0,
+    };
+    Transaction {
+        version: 0,
+        inputs: vec![TxIn { prev_txid: to_spend.txid(), prev_vout: 0, script_sig: Vec::new(), sequence: 0, witness: Vec::new() }],
+        outputs: vec![TxOut { value: 0, script_pubkey: vec![OP_RETURN] }],
+        lock_time: 0,
+    }
+}
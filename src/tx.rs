@@ -0,0 +1,260 @@
+//! Parsing of serialized Bitcoin transactions.
+
+use alloc::vec::Vec;
+
+use crate::error::VerifyError;
+use crate::hash::hash256;
+
+/// Script prefix of a BIP141 witness commitment output: `OP_RETURN`, a 36-byte
+/// push, and the `aa21a9ed` commitment header.
+const WITNESS_COMMITMENT_PREFIX: [u8; 6] = [0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed];
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxIn {
+    /// Txid of the spent output, internal byte order.
+    pub prev_txid: [u8; 32],
+    pub prev_vout: u32,
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+    /// Witness stack items; empty for non-segwit inputs.
+    pub witness: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxOut {
+    pub value: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transaction {
+    pub version: u32,
+    pub inputs: Vec<TxIn>,
+    pub outputs: Vec<TxOut>,
+    pub lock_time: u32,
+}
+
+impl Transaction {
+    /// Parses a transaction in either legacy or BIP144 segwit serialization.
+    /// Trailing bytes are rejected.
+    pub fn parse(bytes: &[u8]) -> Result<Self, VerifyError> {
+        let mut reader = Reader::new(bytes);
+        let version = reader.read_u32()?;
+
+        // A zero where the input count belongs is the segwit marker, followed by the flag.
+        let segwit = reader.peek() == Some(0);
+        if segwit {
+            reader.take(1)?;
+            if reader.read_u8()? != 1 {
+                return Err(VerifyError::MalformedTx);
+            }
+        }
+
+        let input_count = reader.read_compact_size()?;
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let mut prev_txid = [0u8; 32];
+            prev_txid.copy_from_slice(reader.take(32)?);
+            inputs.push(TxIn {
+                prev_txid,
+                prev_vout: reader.read_u32()?,
+                script_sig: reader.read_var_bytes()?,
+                sequence: reader.read_u32()?,
+                witness: Vec::new(),
+            });
+        }
+
+        let output_count = reader.read_compact_size()?;
+        let mut outputs = Vec::new();
+        for _ in 0..output_count {
+            outputs.push(TxOut {
+                value: reader.read_u64()?,
+                script_pubkey: reader.read_var_bytes()?,
+            });
+        }
+
+        if segwit {
+            for input in inputs.iter_mut() {
+                let items = reader.read_compact_size()?;
+                for _ in 0..items {
+                    input.witness.push(reader.read_var_bytes()?);
+                }
+            }
+        }
+
+        let lock_time = reader.read_u32()?;
+        if !reader.is_empty() {
+            return Err(VerifyError::MalformedTx);
+        }
+
+        Ok(Self { version, inputs, outputs, lock_time })
+    }
+
+    /// Returns true if any input carries witness data.
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+    }
+
+    /// Serializes the transaction without witness data, the form its txid commits to.
+    pub fn serialize_without_witness(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.version.to_le_bytes());
+        write_compact_size(&mut out, self.inputs.len() as u64);
+        for input in &self.inputs {
+            out.extend_from_slice(&input.prev_txid);
+            out.extend_from_slice(&input.prev_vout.to_le_bytes());
+            write_var_bytes(&mut out, &input.script_sig);
+            out.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut out, self.outputs.len() as u64);
+        for output in &self.outputs {
+            out.extend_from_slice(&output.value.to_le_bytes());
+            write_var_bytes(&mut out, &output.script_pubkey);
+        }
+        out.extend_from_slice(&self.lock_time.to_le_bytes());
+        out
+    }
+
+    /// BIP141 weight: four units per byte of the serialization without witness,
+    /// plus one per byte of witness data including the marker and flag.
+    pub fn weight(&self) -> usize {
+        let mut witness = Vec::new();
+        if self.has_witness() {
+            witness.extend_from_slice(&[0x00, 0x01]);
+            for input in &self.inputs {
+                write_compact_size(&mut witness, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_var_bytes(&mut witness, item);
+                }
+            }
+        }
+        self.serialize_without_witness().len() * 4 + witness.len()
+    }
+
+    /// Virtual size in vbytes: the weight divided by four, rounded up.
+    pub fn vsize(&self) -> usize {
+        self.weight().div_ceil(4)
+    }
+
+    /// The transaction id, internal byte order.
+    pub fn txid(&self) -> [u8; 32] {
+        hash256(&self.serialize_without_witness())
+    }
+
+    /// Returns the 32-byte witness commitment from the last output carrying one,
+    /// the one BIP141 says counts. An output that starts like a commitment but is
+    /// too short to hold one is rejected with `MalformedTx`.
+    pub fn witness_commitment(&self) -> Result<Option<[u8; 32]>, VerifyError> {
+        let mut commitment = None;
+        for output in &self.outputs {
+            let script = &output.script_pubkey;
+            if script.starts_with(&WITNESS_COMMITMENT_PREFIX) {
+                let bytes = script.get(6..38).ok_or(VerifyError::MalformedTx)?;
+                let mut value = [0u8; 32];
+                value.copy_from_slice(bytes);
+                commitment = Some(value);
+            }
+        }
+        Ok(commitment)
+    }
+
+    /// Returns the witness reserved value of a coinbase: the single 32-byte item
+    /// on its input's witness stack.
+    pub fn witness_reserved_value(&self) -> Option<[u8; 32]> {
+        match self.inputs.first().map(|input| input.witness.as_slice()) {
+            Some([item]) if item.len() == 32 => {
+                let mut value = [0u8; 32];
+                value.copy_from_slice(item);
+                Some(value)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Cursor over a byte slice that fails with `MalformedTx` on truncation.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], VerifyError> {
+        let end = self.pos.checked_add(len).ok_or(VerifyError::MalformedTx)?;
+        let slice = self.data.get(self.pos..end).ok_or(VerifyError::MalformedTx)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    pub(crate) fn read_u8(&mut self) -> Result<u8, VerifyError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_u16(&mut self) -> Result<u16, VerifyError> {
+        let mut word = [0u8; 2];
+        word.copy_from_slice(self.take(2)?);
+        Ok(u16::from_le_bytes(word))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32, VerifyError> {
+        let mut word = [0u8; 4];
+        word.copy_from_slice(self.take(4)?);
+        Ok(u32::from_le_bytes(word))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64, VerifyError> {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(word))
+    }
+
+    pub(crate) fn read_compact_size(&mut self) -> Result<u64, VerifyError> {
+        match self.read_u8()? {
+            0xfd => Ok(self.read_u16()? as u64),
+            0xfe => Ok(self.read_u32()? as u64),
+            0xff => self.read_u64(),
+            n => Ok(n as u64),
+        }
+    }
+
+    pub(crate) fn read_var_bytes(&mut self) -> Result<Vec<u8>, VerifyError> {
+        let len = self.read_compact_size()?;
+        let len = usize::try_from(len).map_err(|_| VerifyError::MalformedTx)?;
+        Ok(self.take(len)?.to_vec())
+    }
+}
+
+pub(crate) fn write_compact_size(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+pub(crate) fn write_var_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_compact_size(out, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}